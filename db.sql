@@ -1,12 +1,12 @@
 create table "user"
 (
-    id              uuid                                   not null
+    id                uuid                                        not null
         primary key,
-    email           varchar                                not null,
-    name            varchar                                not null,
-    created_at      timestamp with time zone default now() not null,
-    registered_at   timestamp with time zone,
-    discord_webhook varchar
+    email             varchar                                     not null,
+    name              varchar                                     not null,
+    created_at        timestamp with time zone default now()      not null,
+    registered_at     timestamp with time zone,
+    expiry_thresholds varchar                  default '30,14,7,1' not null
 );
 
 alter table "user"
@@ -41,12 +41,48 @@ create table website
     ssl_expire_at          timestamp with time zone,
     is_deleted             boolean                  default false             not null,
     last_ssl_checked_at    timestamp with time zone,
-    is_paused              boolean                  default false             not null
+    is_paused              boolean                  default false             not null,
+    auth_type              varchar,
+    auth_username          varchar,
+    auth_password          varchar,
+    headers                varchar                  default ''                not null,
+    ip_family              varchar                  default 'any'             not null,
+    last_audit_at          timestamp with time zone,
+    crawl_enabled          boolean                  default false             not null,
+    crawl_depth            integer                  default 2                 not null,
+    last_crawled_at        timestamp with time zone,
+    min_size               bigint,
+    max_size               bigint,
+    max_assets             integer,
+    max_assets_size        bigint,
+    escalation_policy_id   uuid,
+    reminder_interval      integer,
+    reminder_limit         integer,
+    domain_check_failures  integer                  default 0                 not null,
+    ssl_check_failures     integer                  default 0                 not null,
+    is_flapping            boolean                  default false             not null,
+    badge_token            varchar
+        unique
 );
 
 alter table website
     owner to postgres;
 
+create table secret
+(
+    id         uuid                     default gen_random_uuid() not null
+        primary key,
+    user_id    uuid                                               not null
+        references "user",
+    name       varchar                                            not null,
+    value      varchar                                            not null,
+    created_at timestamp with time zone default now()             not null,
+    unique (user_id, name)
+);
+
+alter table secret
+    owner to postgres;
+
 
 
 create table website_state
@@ -57,9 +93,342 @@ create table website_state
         primary key,
     website_id uuid                                               not null
         references website,
-    duration   bigint                   default 0                 not null
+    duration   bigint                   default 0                 not null,
+    reason     varchar
 );
 
 alter table website_state
     owner to postgres;
 
+
+create table security_audit
+(
+    id                     uuid                     default gen_random_uuid() not null
+        primary key,
+    website_id             uuid                                               not null
+        references website,
+    created_at             timestamp with time zone default now()             not null,
+    hsts                   varchar,
+    csp                    varchar,
+    x_frame_options        varchar,
+    x_content_type_options varchar,
+    cookie_flags           varchar                  default ''                not null,
+    tls_versions           varchar                  default ''                not null,
+    issues                 varchar                  default ''                not null
+);
+
+alter table security_audit
+    owner to postgres;
+
+create table crawl_run
+(
+    id          uuid                     default gen_random_uuid() not null
+        primary key,
+    website_id  uuid                                               not null
+        references website,
+    created_at  timestamp with time zone default now()             not null,
+    finished_at timestamp with time zone,
+    pages       integer                  default 0                 not null
+);
+
+alter table crawl_run
+    owner to postgres;
+
+create table crawl_issue
+(
+    id           uuid default gen_random_uuid() not null
+        primary key,
+    crawl_run_id uuid                           not null
+        references crawl_run,
+    url          varchar                        not null,
+    status       integer,
+    error        varchar
+);
+
+alter table crawl_issue
+    owner to postgres;
+
+create table notification_channel
+(
+    id                uuid                     default gen_random_uuid() not null
+        primary key,
+    user_id           uuid                                               not null
+        references "user",
+    kind              varchar                                            not null,
+    name              varchar                                            not null,
+    config            jsonb                    default '{}'::jsonb       not null,
+    created_at        timestamp with time zone default now()             not null,
+    reminder_interval integer,
+    reminder_limit    integer
+);
+
+alter table notification_channel
+    owner to postgres;
+
+create table website_channel
+(
+    website_id uuid not null
+        references website,
+    channel_id uuid not null
+        references notification_channel,
+    primary key (website_id, channel_id)
+);
+
+alter table website_channel
+    owner to postgres;
+
+create table notification_delivery
+(
+    id              uuid                     default gen_random_uuid() not null
+        primary key,
+    channel_id      uuid                                               not null
+        references notification_channel,
+    website_id      uuid                                               not null,
+    payload         jsonb                                              not null,
+    status          integer,
+    error           varchar,
+    attempt         integer                  default 1                 not null,
+    state           varchar                                            not null,
+    next_attempt_at timestamp with time zone,
+    created_at      timestamp with time zone default now()             not null
+);
+
+alter table notification_delivery
+    owner to postgres;
+
+create table escalation_policy
+(
+    id         uuid                     default gen_random_uuid() not null
+        primary key,
+    user_id    uuid                                               not null
+        references "user",
+    name       varchar                                            not null,
+    created_at timestamp with time zone default now()             not null
+);
+
+alter table escalation_policy
+    owner to postgres;
+
+create table escalation_step
+(
+    id            uuid    default gen_random_uuid() not null
+        primary key,
+    policy_id     uuid                              not null
+        references escalation_policy,
+    channel_id    uuid                              not null
+        references notification_channel,
+    delay_minutes integer default 0                 not null
+);
+
+alter table escalation_step
+    owner to postgres;
+
+alter table website
+    add foreign key (escalation_policy_id) references escalation_policy;
+
+create table incident
+(
+    id              uuid                     default gen_random_uuid() not null
+        primary key,
+    website_id      uuid                                               not null
+        references website,
+    opened_at       timestamp with time zone default now()             not null,
+    resolved_at     timestamp with time zone,
+    acknowledged_at timestamp with time zone,
+    acknowledged_by varchar,
+    escalation_step integer                  default 0                 not null,
+    root_cause      varchar,
+    assignee        varchar
+);
+
+alter table incident
+    owner to postgres;
+
+create table incident_reminder
+(
+    incident_id  uuid                                   not null
+        references incident,
+    channel_id   uuid                                   not null
+        references notification_channel,
+    sent         integer                  default 0     not null,
+    last_sent_at timestamp with time zone default now() not null,
+    primary key (incident_id, channel_id)
+);
+
+alter table incident_reminder
+    owner to postgres;
+
+create table expiry_alert
+(
+    website_id uuid                                   not null
+        references website,
+    kind       varchar                                not null,
+    expire_at  timestamp with time zone               not null,
+    threshold  integer                                not null,
+    created_at timestamp with time zone default now() not null,
+    primary key (website_id, kind, expire_at, threshold)
+);
+
+alter table expiry_alert
+    owner to postgres;
+
+create table incident_event
+(
+    id          uuid                     default gen_random_uuid() not null
+        primary key,
+    incident_id uuid                                               not null
+        references incident,
+    kind        varchar                                            not null,
+    message     varchar                                            not null,
+    author      varchar,
+    created_at  timestamp with time zone default now()             not null
+);
+
+alter table incident_event
+    owner to postgres;
+
+create table maintenance_window
+(
+    id               uuid                     default gen_random_uuid() not null
+        primary key,
+    user_id          uuid                                               not null
+        references "user",
+    name             varchar                                            not null,
+    website_id       uuid
+        references website,
+    tag              varchar,
+    starts_at        timestamp with time zone                           not null,
+    duration_minutes integer                                            not null,
+    rrule            varchar,
+    created_at       timestamp with time zone default now()             not null
+);
+
+alter table maintenance_window
+    owner to postgres;
+
+create table website_dependency
+(
+    website_id uuid not null
+        references website,
+    parent_id  uuid not null
+        references website,
+    primary key (website_id, parent_id)
+);
+
+alter table website_dependency
+    owner to postgres;
+
+create table status_page
+(
+    id                 uuid                     default gen_random_uuid() not null
+        primary key,
+    user_id            uuid                                               not null
+        unique
+        references "user",
+    slug               varchar                                            not null
+        unique,
+    title              varchar                                            not null,
+    logo_url           varchar,
+    brand_color        varchar                  default '#00d1b2'         not null,
+    hide_urls          boolean                  default false             not null,
+    custom_domain      varchar,
+    domain_verified_at timestamp with time zone,
+    created_at         timestamp with time zone default now()             not null
+);
+
+alter table status_page
+    owner to postgres;
+
+create unique index status_page_verified_domain
+    on status_page (custom_domain)
+    where domain_verified_at is not null;
+
+create table status_page_section
+(
+    id             uuid    default gen_random_uuid() not null
+        primary key,
+    status_page_id uuid                              not null
+        references status_page,
+    name           varchar                           not null,
+    position       integer default 0                 not null
+);
+
+alter table status_page_section
+    owner to postgres;
+
+create table status_page_monitor
+(
+    status_page_id uuid    not null
+        references status_page,
+    website_id     uuid    not null
+        references website,
+    section_id     uuid
+        references status_page_section,
+    display_name   varchar,
+    primary key (status_page_id, website_id)
+);
+
+alter table status_page_monitor
+    owner to postgres;
+
+create table status_page_announcement
+(
+    id             uuid                     default gen_random_uuid() not null
+        primary key,
+    status_page_id uuid                                               not null
+        references status_page,
+    kind           varchar                                            not null,
+    title          varchar                                            not null,
+    status         varchar                                            not null,
+    starts_at      timestamp with time zone,
+    ends_at        timestamp with time zone,
+    created_at     timestamp with time zone default now()             not null,
+    updated_at     timestamp with time zone default now()             not null
+);
+
+alter table status_page_announcement
+    owner to postgres;
+
+create table status_page_update
+(
+    id              uuid                     default gen_random_uuid() not null
+        primary key,
+    announcement_id uuid                                               not null
+        references status_page_announcement,
+    status          varchar                                            not null,
+    message         varchar                                            not null,
+    created_at      timestamp with time zone default now()             not null
+);
+
+alter table status_page_update
+    owner to postgres;
+
+create table status_page_subscriber
+(
+    id                   uuid                     default gen_random_uuid() not null
+        primary key,
+    status_page_id       uuid                                               not null
+        references status_page,
+    email                varchar                                            not null,
+    confirmed_at         timestamp with time zone,
+    confirmation_sent_at timestamp with time zone,
+    requested_ip         varchar,
+    created_at           timestamp with time zone default now()             not null,
+    unique (status_page_id, email)
+);
+
+alter table status_page_subscriber
+    owner to postgres;
+
+create table website_response
+(
+    website_id uuid                     not null
+        references website,
+    hour       timestamp with time zone not null,
+    total_ms   bigint                   not null,
+    checks     integer                  not null,
+    primary key (website_id, hour)
+);
+
+alter table website_response
+    owner to postgres;
@@ -0,0 +1,15 @@
+-- databases created before notification channels keep a discord_webhook per user,
+-- run once before starting this version to move each webhook into a Discord channel.
+-- monitors without linked channels alert every channel of their user, so no
+-- website_channel rows are needed. the webhook is stored as is, channel secrets
+-- that don't decrypt are used as plain values.
+begin;
+
+insert into notification_channel (user_id, kind, name, config)
+select id, 'discord', 'Discord', jsonb_build_object('webhook', discord_webhook)
+from "user"
+where discord_webhook is not null and discord_webhook <> '';
+
+alter table "user" drop column discord_webhook;
+
+commit;
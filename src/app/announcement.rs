@@ -0,0 +1,851 @@
+use super::layout;
+use super::mail::{self, AlertMail};
+use super::status_page::{page_url, StatusPage};
+use crate::{appconfig::ENV, crypto, logged_user::LoggedUser, AppState};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::{delete, get, post};
+use axum::{extract::*, response::*, Router};
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use maud::{html, Markup, PreEscaped};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+pub const INCIDENT_STATUSES: [&str; 4] = ["investigating", "identified", "monitoring", "resolved"];
+pub const MAINTENANCE_STATUSES: [&str; 3] = ["scheduled", "in_progress", "completed"];
+const FEED_ITEMS: i64 = 50;
+const SUBSCRIPTION_ACTIONS: [&str; 2] = ["confirm", "unsubscribe"];
+// the subscribe form is public, so confirmation mails are limited per address, page and ip
+const CONFIRMATION_COOLDOWN_MINUTES: i64 = 60;
+const PAGE_CONFIRMATIONS_PER_HOUR: i64 = 20;
+const IP_CONFIRMATIONS_PER_HOUR: i64 = 5;
+
+fn statuses(kind: &str) -> &'static [&'static str] {
+    match kind {
+        "maintenance" => &MAINTENANCE_STATUSES,
+        _ => &INCIDENT_STATUSES,
+    }
+}
+
+fn is_closed(status: &str) -> bool {
+    status == "resolved" || status == "completed"
+}
+
+fn status_label(status: &str) -> String {
+    let status = status.replace('_', " ");
+    let mut chars = status.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => status,
+    }
+}
+
+fn status_color(status: &str) -> &'static str {
+    match status {
+        "investigating" => "#f14668",
+        "identified" => "#ffb70f",
+        "monitoring" | "scheduled" | "in_progress" => "#3e8ed0",
+        _ => "#48c78e",
+    }
+}
+
+// announcements still open plus the ones closed in the last days, with their updates
+pub async fn panel(db: &PgPool, page: &StatusPage, days: i64) -> Markup {
+    let announcements = sqlx::query!(
+        r#"select id, kind, title, status, starts_at, ends_at from status_page_announcement
+        where status_page_id = $1
+            and (status not in ('resolved', 'completed') or updated_at > $2)
+        order by created_at desc"#,
+        page.id,
+        Utc::now() - Duration::days(days)
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let ids: Vec<Uuid> = announcements.iter().map(|a| a.id).collect();
+    let updates = sqlx::query!(
+        r#"select announcement_id, status, message, created_at from status_page_update
+        where announcement_id = any($1)
+        order by created_at desc"#,
+        &ids
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    html! {
+        @for announcement in announcements.iter() {
+            div class="box" style={"border-left: 4px solid " (status_color(&announcement.status))} {
+                div class="is-flex is-justify-content-space-between" {
+                    h2 class="title is-size-5 mb-2" { (announcement.title) }
+                    span class="tag" { (status_label(&announcement.status)) }
+                }
+                @if let (Some(starts_at), Some(ends_at)) = (announcement.starts_at, announcement.ends_at) {
+                    p class="is-size-7 has-text-grey mb-2" {
+                        "Scheduled from " (starts_at.format("%H:%M %d/%m/%Y").to_string())
+                        " to " (ends_at.format("%H:%M %d/%m/%Y UTC").to_string())
+                    }
+                }
+                @for update in updates.iter().filter(|u| u.announcement_id == announcement.id) {
+                    div class="mb-2" {
+                        p {
+                            span class="has-text-weight-semibold" { (status_label(&update.status)) " - " }
+                            (update.message)
+                        }
+                        p class="is-size-7 has-text-grey" {
+                            (update.created_at.format("%H:%M %d/%m/%Y UTC").to_string())
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn subscribe_box(page: &StatusPage) -> Markup {
+    html! {
+        div class="box" {
+            h2 class="title is-size-5" { "Subscribe to Updates" }
+            form hx-post={"/status/"(page.slug)"/subscribe"} hx-swap="outerHTML" {
+                div class="field has-addons" {
+                    div class="control flex1" {
+                        input class="input" type="email" name="email" placeholder="you@example.com" required {}
+                    }
+                    div class="control" {
+                        button type="submit" class="button is-primary" { "Subscribe" }
+                    }
+                }
+            }
+            p class="is-size-7 mt-2" {
+                "Or follow the "
+                a href={"/status/"(page.slug)"/feed.rss"} { "RSS" }
+                " or "
+                a href={"/status/"(page.slug)"/feed.atom"} { "Atom" }
+                " feed"
+            }
+        }
+    }
+}
+
+fn subscriber_token(subscriber_id: Uuid) -> String {
+    let payload = subscriber_id.to_string();
+    format!(
+        "{}.{}",
+        payload,
+        crypto::sign(&ENV.secret, &format!("subscriber.{}", payload))
+    )
+}
+
+fn verify_subscriber_token(token: &str) -> Option<Uuid> {
+    let (payload, signature) = token.split_once('.')?;
+    if !crypto::verify(&ENV.secret, &format!("subscriber.{}", payload), signature) {
+        return None;
+    }
+    payload.parse().ok()
+}
+
+fn subscriber_link(page: &StatusPage, action: &str, subscriber_id: Uuid) -> String {
+    format!(
+        "{}/status/{}/{}/{}",
+        ENV.host,
+        page.slug,
+        action,
+        subscriber_token(subscriber_id)
+    )
+}
+
+async fn find_page(db: &PgPool, slug: &str) -> Option<StatusPage> {
+    sqlx::query_as!(
+        StatusPage,
+        r#"select id, slug, title, logo_url, brand_color, hide_urls,
+            custom_domain, domain_verified_at
+        from status_page where slug = $1"#,
+        slug
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap()
+}
+
+// every confirmed subscriber gets its own mail so the unsubscribe link is personal
+async fn notify_subscribers(
+    db: PgPool,
+    page_id: Uuid,
+    title: String,
+    status: String,
+    message: String,
+) {
+    let page = sqlx::query_as!(
+        StatusPage,
+        r#"select id, slug, title, logo_url, brand_color, hide_urls,
+            custom_domain, domain_verified_at
+        from status_page where id = $1"#,
+        page_id
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    let subscribers = sqlx::query!(
+        r#"select id, email from status_page_subscriber
+        where status_page_id = $1 and confirmed_at is not null"#,
+        page_id
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+
+    for subscriber in subscribers {
+        let sent = mail::send_alert_mail(
+            vec![subscriber.email.clone()],
+            AlertMail {
+                subject: format!("[{}] {}: {}", page.title, status_label(&status), title),
+                title: format!("{}: {}", status_label(&status), title),
+                message: format!(
+                    "{}\n\nUnsubscribe: {}",
+                    message,
+                    subscriber_link(&page, "unsubscribe", subscriber.id)
+                ),
+                link: page_url(&page),
+                button: "View status page".to_string(),
+                color: status_color(&status).to_string(),
+            },
+        )
+        .await;
+        if let Err(e) = sent {
+            println!("{} subscriber mail error {}", subscriber.email, e);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SubscribeParams {
+    pub email: String,
+}
+
+// the proxy in front of the app sets x-real-ip and appends the peer it saw to
+// x-forwarded-for, earlier entries come from the client and can't be trusted
+fn client_ip(headers: &HeaderMap, peer: SocketAddr) -> String {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.rsplit(',').next())
+            .map(|ip| ip.trim().to_string())
+            .filter(|ip| !ip.is_empty())
+    };
+    header("x-real-ip")
+        .or(header("x-forwarded-for"))
+        .unwrap_or(peer.ip().to_string())
+}
+
+fn subscribe_message(message: &str) -> Response {
+    html! { p class="has-text-weight-semibold" { (message) } }
+        .into_string()
+        .into_response()
+}
+
+pub async fn subscribe(
+    State(db): State<PgPool>,
+    Path(slug): Path<String>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(params): Form<SubscribeParams>,
+) -> impl IntoResponse {
+    let Some(page) = find_page(&db, &slug).await else {
+        return (StatusCode::NOT_FOUND, "Status page not found").into_response();
+    };
+    let email = params.email.trim().to_lowercase();
+    let ip = client_ip(&headers, peer);
+    let hour_ago = Utc::now() - Duration::hours(1);
+
+    let sent = sqlx::query!(
+        r#"select count(*) filter (where status_page_id = $1) as "page!",
+            count(*) filter (where requested_ip = $2) as "ip!"
+        from status_page_subscriber where confirmation_sent_at > $3"#,
+        page.id,
+        ip,
+        hour_ago
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    if sent.page >= PAGE_CONFIRMATIONS_PER_HOUR || sent.ip >= IP_CONFIRMATIONS_PER_HOUR {
+        return subscribe_message("Too many subscription requests, try again later");
+    }
+
+    let subscriber = sqlx::query!(
+        r#"insert into status_page_subscriber (status_page_id, email) values ($1, $2)
+        on conflict (status_page_id, email) do update set email = excluded.email
+        returning id, confirmed_at"#,
+        page.id,
+        email
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    if subscriber.confirmed_at.is_some() {
+        return subscribe_message("You are already subscribed");
+    }
+
+    // claimed before sending, so concurrent requests can't send twice within the cooldown
+    let claimed = sqlx::query!(
+        r#"update status_page_subscriber set confirmation_sent_at = now(), requested_ip = $2
+        where id = $1 and (confirmation_sent_at is null or confirmation_sent_at < $3)
+        returning id"#,
+        subscriber.id,
+        ip,
+        Utc::now() - Duration::minutes(CONFIRMATION_COOLDOWN_MINUTES)
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    if claimed.is_none() {
+        return subscribe_message("A confirmation mail was already sent, check your inbox");
+    }
+
+    let sent = mail::send_alert_mail(
+        vec![email.clone()],
+        AlertMail {
+            subject: format!("Confirm your subscription to {}", page.title),
+            title: format!("Confirm your subscription to {}", page.title),
+            message: "You will receive incident and maintenance updates once confirmed."
+                .to_string(),
+            link: subscriber_link(&page, "confirm", subscriber.id),
+            button: "Confirm subscription".to_string(),
+            color: page.brand_color.clone(),
+        },
+    )
+    .await;
+
+    match sent {
+        Ok(_) => subscribe_message("Check your inbox to confirm the subscription"),
+        Err(_) => subscribe_message("The confirmation mail could not be sent to this address"),
+    }
+}
+
+fn subscription_page(title: &str, body: Markup) -> Html<String> {
+    layout::page(
+        html! {},
+        html! {
+            app style="width: 100vw; height: 100vh; display: flex; flex-direction:column;
+                justify-content: center; align-items: center" {
+                h5 class="title is-size-4" { (title) }
+                (body)
+            }
+        },
+    )
+}
+
+// the links only show a confirmation, so link previews and mail scanners can't subscribe
+pub async fn get_subscription(
+    State(db): State<PgPool>,
+    Path((slug, action, token)): Path<(String, String, String)>,
+) -> impl IntoResponse {
+    let (Some(page), Some(_)) = (find_page(&db, &slug).await, verify_subscriber_token(&token))
+    else {
+        return subscription_page("This link is invalid", html! {});
+    };
+    if !SUBSCRIPTION_ACTIONS.contains(&action.as_str()) {
+        return subscription_page("This link is invalid", html! {});
+    }
+
+    let (title, button) = match action.as_str() {
+        "confirm" => (format!("Subscribe to updates of {}", page.title), "Confirm"),
+        _ => (
+            format!("Unsubscribe from updates of {}", page.title),
+            "Unsubscribe",
+        ),
+    };
+
+    subscription_page(
+        &title,
+        html! {
+            form method="post" style="width: 300px" {
+                button type="submit" class="button is-primary is-fullwidth" { (button) }
+            }
+        },
+    )
+}
+
+pub async fn post_subscription(
+    State(db): State<PgPool>,
+    Path((slug, action, token)): Path<(String, String, String)>,
+) -> impl IntoResponse {
+    let (Some(page), Some(subscriber_id)) =
+        (find_page(&db, &slug).await, verify_subscriber_token(&token))
+    else {
+        return subscription_page("This link is invalid", html! {});
+    };
+    if !SUBSCRIPTION_ACTIONS.contains(&action.as_str()) {
+        return subscription_page("This link is invalid", html! {});
+    }
+
+    let title = if action == "confirm" {
+        sqlx::query!(
+            r#"update status_page_subscriber set confirmed_at = coalesce(confirmed_at, now())
+            where id = $1 and status_page_id = $2"#,
+            subscriber_id,
+            page.id
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        format!("You are subscribed to updates of {}", page.title)
+    } else {
+        sqlx::query!(
+            r#"delete from status_page_subscriber where id = $1 and status_page_id = $2"#,
+            subscriber_id,
+            page.id
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        format!("You are unsubscribed from updates of {}", page.title)
+    };
+
+    subscription_page(
+        &title,
+        html! { a class="subtitle is-size-5" href=(page_url(&page)) { "Back to the status page" } },
+    )
+}
+
+struct FeedItem {
+    id: Uuid,
+    title: String,
+    status: String,
+    message: String,
+    created_at: DateTime<Utc>,
+}
+
+async fn feed_items(db: &PgPool, page_id: Uuid) -> Vec<FeedItem> {
+    sqlx::query_as!(
+        FeedItem,
+        r#"select u.id, a.title, u.status, u.message, u.created_at
+        from status_page_update u
+        join status_page_announcement a on a.id = u.announcement_id
+        where a.status_page_id = $1
+        order by u.created_at desc
+        limit $2"#,
+        page_id,
+        FEED_ITEMS
+    )
+    .fetch_all(db)
+    .await
+    .unwrap()
+}
+
+pub async fn rss(State(db): State<PgPool>, Path(slug): Path<String>) -> impl IntoResponse {
+    let Some(page) = find_page(&db, &slug).await else {
+        return (StatusCode::NOT_FOUND, "Status page not found").into_response();
+    };
+    let items = feed_items(&db, page.id).await;
+    let link = page_url(&page);
+
+    let feed = html! {
+        (PreEscaped(r#"<?xml version="1.0" encoding="UTF-8"?>"#))
+        rss version="2.0" {
+            channel {
+                title { (page.title) }
+                link { (link) }
+                description { "Incident and maintenance updates of " (page.title) }
+                @for item in items.iter() {
+                    item {
+                        title { (status_label(&item.status)) ": " (item.title) }
+                        link { (link) }
+                        description { (item.message) }
+                        pubDate { (item.created_at.to_rfc2822()) }
+                        guid isPermaLink="false" { (item.id.to_string()) }
+                    }
+                }
+            }
+        }
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/rss+xml; charset=utf-8"),
+            (header::CACHE_CONTROL, "public, max-age=300"),
+        ],
+        feed.into_string(),
+    )
+        .into_response()
+}
+
+pub async fn atom(State(db): State<PgPool>, Path(slug): Path<String>) -> impl IntoResponse {
+    let Some(page) = find_page(&db, &slug).await else {
+        return (StatusCode::NOT_FOUND, "Status page not found").into_response();
+    };
+    let items = feed_items(&db, page.id).await;
+    let link = page_url(&page);
+    let updated = items.first().map_or(Utc::now(), |i| i.created_at);
+
+    let feed = html! {
+        (PreEscaped(r#"<?xml version="1.0" encoding="UTF-8"?>"#))
+        feed xmlns="http://www.w3.org/2005/Atom" {
+            title { (page.title) }
+            link href=(link) {}
+            id { "urn:uuid:" (page.id.to_string()) }
+            updated { (updated.to_rfc3339()) }
+            @for item in items.iter() {
+                entry {
+                    title { (status_label(&item.status)) ": " (item.title) }
+                    link href=(link) {}
+                    id { "urn:uuid:" (item.id.to_string()) }
+                    updated { (item.created_at.to_rfc3339()) }
+                    content type="text" { (item.message) }
+                }
+            }
+        }
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/atom+xml; charset=utf-8"),
+            (header::CACHE_CONTROL, "public, max-age=300"),
+        ],
+        feed.into_string(),
+    )
+        .into_response()
+}
+
+async fn announcements_modal(db: &PgPool, user_id: Uuid, error: Option<&str>) -> String {
+    let announcements = sqlx::query!(
+        r#"select a.id, a.kind, a.title, a.status, a.starts_at, a.ends_at
+        from status_page_announcement a
+        join status_page p on p.id = a.status_page_id
+        where p.user_id = $1
+        order by a.status in ('resolved', 'completed'), a.created_at desc
+        limit 30"#,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let subscribers = sqlx::query!(
+        r#"select count(*) as "count!" from status_page_subscriber s
+        join status_page p on p.id = s.status_page_id
+        where p.user_id = $1 and s.confirmed_at is not null"#,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+    .unwrap();
+
+    let form = html! {
+        div class="box" {
+            h2 class="title" { "Announcements" }
+            p class="subtitle is-size-6" {
+                "Updates are shown on the status page, in its feeds and mailed to "
+                (subscribers.count) " confirmed subscribers. Times are in UTC."
+            }
+
+            table class="table is-size-7" style="width: 100%" {
+                tbody {
+                    @for announcement in announcements.iter() {
+                        tr {
+                            td {
+                                (announcement.title)
+                                @if announcement.kind == "maintenance" {
+                                    span class="tag is-info is-light ml-1" { "maintenance" }
+                                }
+                                @if let Some(starts_at) = announcement.starts_at {
+                                    p class="has-text-grey" { (starts_at.format("%H:%M %d/%m/%Y").to_string()) }
+                                }
+                            }
+                            td { span class="tag" { (status_label(&announcement.status)) } }
+                            td {
+                                @if !is_closed(&announcement.status) {
+                                    form class="is-flex" style="gap: 4px"
+                                        hx-post={"/status-page/announcements/"(announcement.id.to_string())"/updates"}
+                                        hx-target="#modal" hx-swap="outerHTML" {
+                                        div class="select is-small" {
+                                            select name="status" {
+                                                @for status in statuses(&announcement.kind) {
+                                                    option value=(status) selected[*status == announcement.status] {
+                                                        (status_label(status))
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        input class="input is-small" name="message" placeholder="Update" required {}
+                                        button type="submit" class="button is-small is-primary" { "Post" }
+                                    }
+                                }
+                            }
+                            td {
+                                button class="button is-danger is-inverted is-small"
+                                    hx-delete={"/status-page/announcements/"(announcement.id.to_string())}
+                                    hx-confirm="Do you want to delete this announcement?"
+                                    hx-target="#modal" hx-swap="outerHTML" {
+                                    i class="fa fa-trash" {}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            form hx-post="/status-page/announcements" hx-target="#modal" hx-swap="outerHTML" {
+                div class="field is-grouped" {
+                    div class="control" {
+                        div class="select" {
+                            select name="kind" {
+                                option value="incident" { "Incident" }
+                                option value="maintenance" { "Maintenance" }
+                            }
+                        }
+                    }
+                    div class="control flex1" {
+                        input class="input" name="title" placeholder="Title" required {}
+                    }
+                }
+                div class="field is-grouped" {
+                    div class="control" {
+                        input class="input" type="datetime-local" name="starts_at" {}
+                    }
+                    div class="control" {
+                        input class="input" type="datetime-local" name="ends_at" {}
+                    }
+                }
+                p class="help mb-3" { "Start and end are only used for scheduled maintenance." }
+                div class="field" {
+                    textarea class="textarea" name="message" rows="2" placeholder="What is happening?" required {}
+                }
+                @if let Some(error) = error {
+                    p class="has-text-danger mb-3" { (error) }
+                }
+                button type="submit" class="button is-primary" { "Post" }
+            }
+
+            div style="width: 100%; display: flex; justify-content: flex-end;" {
+                button class="button is-danger is-light" hx-get=""
+                    hx-target="body" hx-swap="outherHTML"
+                    style="width:100px" {
+                    "Close"
+                }
+            }
+        }
+    };
+
+    (html! {
+        div class="modal is-active" id="modal" {
+            div class="modal-background" {}
+            div class="modal-content" style="width: 800px" {
+                (form)
+            }
+            button class="modal-close is-large" aria-label="close" {}
+        }
+    })
+    .into_string()
+}
+
+pub async fn get_announcements(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+) -> impl IntoResponse {
+    announcements_modal(&db, user_id, None).await
+}
+
+#[derive(Deserialize)]
+pub struct AnnouncementParams {
+    pub kind: String,
+    pub title: String,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub message: String,
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .ok()
+        .map(|d| Utc.from_utc_datetime(&d))
+}
+
+pub async fn create(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Form(params): Form<AnnouncementParams>,
+) -> impl IntoResponse {
+    let kind = if params.kind == "maintenance" {
+        "maintenance"
+    } else {
+        "incident"
+    };
+    let (starts_at, ends_at) = (parse_time(&params.starts_at), parse_time(&params.ends_at));
+
+    if kind == "maintenance" && (starts_at.is_none() || ends_at.is_none()) {
+        return announcements_modal(
+            &db,
+            user_id,
+            Some("Scheduled maintenance needs a start and an end"),
+        )
+        .await;
+    }
+    if starts_at.zip(ends_at).map_or(false, |(s, e)| e <= s) {
+        return announcements_modal(&db, user_id, Some("The end must be after the start")).await;
+    }
+    let status = statuses(kind)[0];
+    let (starts_at, ends_at) = if kind == "maintenance" {
+        (starts_at, ends_at)
+    } else {
+        (None, None)
+    };
+
+    let announcement = sqlx::query!(
+        r#"insert into status_page_announcement (status_page_id, kind, title, status, starts_at, ends_at)
+        select id, $2, $3, $4, $5, $6 from status_page where user_id = $1
+        returning id, status_page_id"#,
+        user_id,
+        kind,
+        params.title.trim(),
+        status,
+        starts_at,
+        ends_at
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let Some(announcement) = announcement else {
+        return announcements_modal(&db, user_id, Some("Set up the status page first")).await;
+    };
+
+    sqlx::query!(
+        r#"insert into status_page_update (announcement_id, status, message) values ($1, $2, $3)"#,
+        announcement.id,
+        status,
+        params.message.trim()
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    tokio::spawn(notify_subscribers(
+        db.clone(),
+        announcement.status_page_id,
+        params.title.trim().to_string(),
+        status.to_string(),
+        params.message.trim().to_string(),
+    ));
+
+    announcements_modal(&db, user_id, None).await
+}
+
+#[derive(Deserialize)]
+pub struct UpdateParams {
+    pub status: String,
+    pub message: String,
+}
+
+pub async fn post_update(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+    Form(params): Form<UpdateParams>,
+) -> impl IntoResponse {
+    let announcement = sqlx::query!(
+        r#"select a.id, a.kind, a.title, a.status_page_id from status_page_announcement a
+        join status_page p on p.id = a.status_page_id
+        where a.id = $1 and p.user_id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let Some(announcement) = announcement else {
+        return announcements_modal(&db, user_id, None).await;
+    };
+    if !statuses(&announcement.kind).contains(&params.status.as_str()) {
+        return announcements_modal(&db, user_id, Some("Invalid status")).await;
+    }
+
+    sqlx::query!(
+        r#"insert into status_page_update (announcement_id, status, message) values ($1, $2, $3)"#,
+        announcement.id,
+        params.status,
+        params.message.trim()
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        r#"update status_page_announcement set status = $2, updated_at = now() where id = $1"#,
+        announcement.id,
+        params.status
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    tokio::spawn(notify_subscribers(
+        db.clone(),
+        announcement.status_page_id,
+        announcement.title,
+        params.status.clone(),
+        params.message.trim().to_string(),
+    ));
+
+    announcements_modal(&db, user_id, None).await
+}
+
+pub async fn delete_announcement(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let announcement = sqlx::query!(
+        r#"select a.id from status_page_announcement a
+        join status_page p on p.id = a.status_page_id
+        where a.id = $1 and p.user_id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    if announcement.is_some() {
+        sqlx::query!(
+            r#"delete from status_page_update where announcement_id = $1"#,
+            id
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        sqlx::query!(r#"delete from status_page_announcement where id = $1"#, id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    announcements_modal(&db, user_id, None).await
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_announcements).post(create))
+        .route("/:id/updates", post(post_update))
+        .route("/:id", delete(delete_announcement))
+}
+
+pub fn public_router() -> Router<AppState> {
+    Router::new()
+        .route("/:slug/subscribe", post(subscribe))
+        .route("/:slug/feed.rss", get(rss))
+        .route("/:slug/feed.atom", get(atom))
+        .route(
+            "/:slug/:action/:token",
+            get(get_subscription).post(post_subscription),
+        )
+}
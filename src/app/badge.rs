@@ -0,0 +1,169 @@
+use crate::{uptime, AppState};
+use axum::http::{header, StatusCode};
+use axum::routing::get;
+use axum::{extract::*, response::*, Router};
+use chrono::{Duration, Utc};
+use maud::html;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+pub const METRICS: [(&str, &str); 4] = [
+    ("status", "status"),
+    ("uptime-24h", "uptime 24h"),
+    ("uptime-30d", "uptime 30d"),
+    ("response", "response"),
+];
+const GREY: &str = "#9f9f9f";
+
+// rough Verdana 11px text width, close enough for shields-style badges
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+fn svg(label: &str, value: &str, color: &str) -> String {
+    let label_width = text_width(label);
+    let value_width = text_width(value);
+    let width = label_width + value_width;
+    let title = format!("{}: {}", label, value);
+
+    html! {
+        svg xmlns="http://www.w3.org/2000/svg" width=(width) height="20" role="img" aria-label=(title) {
+            title { (title) }
+            linearGradient id="s" x2="0" y2="100%" {
+                stop offset="0" stop-color="#bbb" stop-opacity=".1" {}
+                stop offset="1" stop-opacity=".1" {}
+            }
+            clipPath id="r" {
+                rect width=(width) height="20" rx="3" fill="#fff" {}
+            }
+            g clip-path="url(#r)" {
+                rect width=(label_width) height="20" fill="#555" {}
+                rect x=(label_width) width=(value_width) height="20" fill=(color) {}
+                rect width=(width) height="20" fill="url(#s)" {}
+            }
+            g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11" {
+                text x=(label_width / 2) y="15" fill="#010101" fill-opacity=".3" { (label) }
+                text x=(label_width / 2) y="14" { (label) }
+                text x=(label_width + value_width / 2) y="15" fill="#010101" fill-opacity=".3" { (value) }
+                text x=(label_width + value_width / 2) y="14" { (value) }
+            }
+        }
+    }
+    .into_string()
+}
+
+fn state_badge(state: &str) -> (&str, &'static str) {
+    match state {
+        "up" => ("up", "#4c1"),
+        "degraded" => ("degraded", "#dfb317"),
+        "down" | "dependency_down" => ("down", "#e05d44"),
+        "maintenance" => ("maintenance", "#007ec6"),
+        "paused" => ("paused", GREY),
+        _ => ("unknown", GREY),
+    }
+}
+
+fn uptime_color(ratio: Option<f64>) -> &'static str {
+    match ratio {
+        None => GREY,
+        Some(r) if r >= 0.999 => "#4c1",
+        Some(r) if r >= 0.99 => "#97ca00",
+        Some(r) if r >= 0.95 => "#dfb317",
+        Some(_) => "#e05d44",
+    }
+}
+
+fn response_color(ms: i64) -> &'static str {
+    match ms {
+        ms if ms < 500 => "#4c1",
+        ms if ms < 1500 => "#dfb317",
+        _ => "#e05d44",
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BadgeParams {
+    pub metric: Option<String>,
+    pub label: Option<String>,
+}
+
+// the token is the only thing identifying the website, no login needed
+pub async fn get_badge(
+    State(db): State<PgPool>,
+    Path(file): Path<String>,
+    Query(params): Query<BadgeParams>,
+) -> impl IntoResponse {
+    let Some(token) = file.strip_suffix(".svg") else {
+        return (StatusCode::NOT_FOUND, "Badge not found").into_response();
+    };
+
+    let website = sqlx::query!(
+        r#"select ws.id, last.state as "state?" from website ws
+        left join lateral (
+            select state from website_state
+            where website_id = ws.id
+            order by created_at desc limit 1
+        ) as last on true
+        where ws.badge_token = $1"#,
+        token
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let Some(website) = website else {
+        return (StatusCode::NOT_FOUND, "Badge not found").into_response();
+    };
+
+    let metric = params.metric.unwrap_or("status".to_string());
+    let Some((metric, default_label)) = METRICS.iter().find(|(m, _)| *m == metric) else {
+        return (StatusCode::NOT_FOUND, "Badge not found").into_response();
+    };
+    let label: String = params
+        .label
+        .filter(|l| !l.trim().is_empty())
+        .unwrap_or(default_label.to_string())
+        .chars()
+        .take(40)
+        .collect();
+
+    let (value, color) = match *metric {
+        "uptime-24h" | "uptime-30d" => {
+            let from = Utc::now()
+                - if *metric == "uptime-24h" {
+                    Duration::hours(24)
+                } else {
+                    Duration::days(30)
+                };
+            let spans = uptime::spans(&db, &[website.id], from).await;
+            let ratio = spans
+                .get(&website.id)
+                .and_then(|spans| uptime::ratio(spans, from, Utc::now()));
+            (uptime::percent(ratio), uptime_color(ratio))
+        }
+        "response" => {
+            match uptime::average_response(&db, website.id, Utc::now() - Duration::hours(24)).await
+            {
+                Some(ms) => (format!("{}ms", ms), response_color(ms)),
+                None => ("-".to_string(), GREY),
+            }
+        }
+        _ => {
+            let (value, color) = state_badge(website.state.as_deref().unwrap_or(""));
+            (value.to_string(), color)
+        }
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, "image/svg+xml"),
+            (header::CACHE_CONTROL, "public, max-age=300"),
+        ],
+        svg(&label, &value, color),
+    )
+        .into_response()
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/:file", get(get_badge))
+}
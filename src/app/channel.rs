@@ -0,0 +1,378 @@
+use crate::notification::{self, Channel, Event, SendError};
+use crate::{crypto, logged_user::LoggedUser, AppState};
+use axum::routing::{delete, get, post};
+use axum::{extract::*, response::*, Router};
+use maud::{html, Markup};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+fn kind_fields(kind: &str) -> Markup {
+    let Some(kind) = notification::kind(kind) else {
+        return html! {};
+    };
+
+    html! {
+        @for field in kind.fields {
+            div class="field" {
+                label class="label" { (field.label) }
+                div class="control" {
+                    input class="input" type=(if field.secret { "password" } else { "text" })
+                        name=(field.name) placeholder=(field.placeholder) autocomplete="off" {}
+                }
+            }
+        }
+    }
+}
+
+pub async fn get_channels(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+) -> impl IntoResponse {
+    let channels = sqlx::query!(
+        r#"select id, kind, name, reminder_interval, reminder_limit
+            from notification_channel where user_id = $1 order by name"#,
+        user_id
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+
+    let first_kind = notification::KINDS[0].id;
+
+    let form = html! {
+        div class="box" {
+            h2 class="title" { "Notification Channels" }
+
+            table class="table" style="width: 100%" {
+                tbody {
+                    @for channel in channels.iter() {
+                        tr {
+                            td { (channel.name) }
+                            td {
+                                span class="tag" {
+                                    (notification::kind(&channel.kind).map(|k| k.name).unwrap_or(&channel.kind))
+                                }
+                            }
+                            td class="is-size-7" {
+                                @if let Some(interval) = channel.reminder_interval {
+                                    "remind every " (interval) "m"
+                                    @if let Some(limit) = channel.reminder_limit { ", " (limit) " times" }
+                                }
+                            }
+                            td id={"test-"(channel.id.to_string())} {}
+                            td {
+                                div class="is-flex" {
+                                    button class="button is-primary is-inverted is-small"
+                                        hx-post={"/channel/"(channel.id.to_string())"/test"}
+                                        hx-target={"#test-"(channel.id.to_string())}
+                                        aria-label="Send test notification" data-cooltipz-dir="bottom" {
+                                        i class="fa fa-paper-plane" {}
+                                    }
+                                    button class="button is-danger is-inverted is-small"
+                                        hx-delete={"/channel/"(channel.id.to_string())}
+                                        hx-confirm="Do you want to delete this channel?"
+                                        hx-target="#modal" hx-swap="outerHTML" {
+                                        i class="fa fa-trash" {}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            form hx-post="/channel" hx-target="#modal" hx-swap="outerHTML" {
+                h6 class="title is-size-5" { "Add Channel" }
+                div class="field is-grouped" {
+                    div class="control" {
+                        div class="select" {
+                            select name="kind" hx-get="/channel/fields" hx-target="#channel-fields" {
+                                @for kind in notification::KINDS {
+                                    option value=(kind.id) { (kind.name) }
+                                }
+                            }
+                        }
+                    }
+                    div class="control flex1" {
+                        input class="input" name="name" placeholder="Name" required {}
+                    }
+                }
+
+                div id="channel-fields" {
+                    (kind_fields(first_kind))
+                }
+
+                div class="field is-grouped" {
+                    div class="control flex1" {
+                        input class="input" type="number" min="1" name="reminder_interval"
+                            placeholder="Remind every minutes" {}
+                    }
+                    div class="control flex1" {
+                        input class="input" type="number" min="1" name="reminder_limit"
+                            placeholder="At most times" {}
+                    }
+                }
+                p class="help mb-3" { "Optional, overrides the reminders of the monitors." }
+
+                div style="width: 100%; display: flex; justify-content: space-between;" {
+                    button type="submit" class="button is-primary" { "Add" }
+                    button type="button" class="button is-danger is-light" hx-get=""
+                        hx-target="body" hx-swap="outherHTML"
+                        style="width:100px" {
+                        "Close"
+                    }
+                }
+            }
+        }
+    };
+
+    (html! {
+        div class="modal is-active" id="modal" {
+            div class="modal-background" {}
+            div class="modal-content" {
+                (form)
+            }
+            button class="modal-close is-large" aria-label="close" {}
+        }
+    })
+    .into_string()
+}
+
+#[derive(Deserialize)]
+pub struct FieldsQuery {
+    pub kind: String,
+}
+
+pub async fn get_fields(
+    LoggedUser(_): LoggedUser,
+    Query(query): Query<FieldsQuery>,
+) -> impl IntoResponse {
+    kind_fields(&query.kind).into_string()
+}
+
+pub async fn create(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Form(params): Form<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let kind = params
+        .get("kind")
+        .and_then(|k| notification::kind(k));
+    let name = params.get("name").cloned().unwrap_or_default();
+    let reminder_interval = params
+        .get("reminder_interval")
+        .and_then(|v| v.trim().parse::<i32>().ok());
+    let reminder_limit = params
+        .get("reminder_limit")
+        .and_then(|v| v.trim().parse::<i32>().ok());
+
+    if let Some(kind) = kind {
+        let mut config = Map::new();
+        for field in kind.fields {
+            let value = params.get(field.name).cloned().unwrap_or_default();
+            let value = value.trim().to_string();
+            let value = if field.secret && !value.is_empty() {
+                crypto::encrypt(&value)
+            } else {
+                value
+            };
+            config.insert(field.name.to_string(), Value::String(value));
+        }
+
+        sqlx::query!(
+            r#"insert into notification_channel
+                (user_id, kind, name, config, reminder_interval, reminder_limit)
+                values ($1, $2, $3, $4, $5, $6)"#,
+            user_id,
+            kind.id,
+            name,
+            Value::Object(config),
+            reminder_interval,
+            reminder_limit
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+    }
+
+    get_channels(State(db), LoggedUser(user_id)).await
+}
+
+pub async fn delete_channel(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"delete from website_channel where channel_id = $1
+            and channel_id in (select id from notification_channel where user_id = $2)"#,
+        id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        r#"delete from incident_reminder where channel_id = $1
+            and channel_id in (select id from notification_channel where user_id = $2)"#,
+        id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        r#"delete from escalation_step where channel_id = $1
+            and channel_id in (select id from notification_channel where user_id = $2)"#,
+        id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        r#"delete from notification_delivery where channel_id = $1
+            and channel_id in (select id from notification_channel where user_id = $2)"#,
+        id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        r#"delete from notification_channel where id = $1 and user_id = $2"#,
+        id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    get_channels(State(db), LoggedUser(user_id)).await
+}
+
+pub async fn test(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let channel = sqlx::query_as!(
+        Channel,
+        r#"select c.id, c.kind, c.name, c.config, u.email as owner_email
+            from notification_channel c
+            join "user" u on u.id = c.user_id
+            where c.id = $1 and c.user_id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_one(&db)
+    .await;
+
+    let result = match channel {
+        Ok(channel) => channel.send(&Event::test()).await,
+        Err(_) => Err(SendError::from("channel not found".to_string())),
+    };
+
+    match result {
+        Ok(_) => html! { span class="has-text-primary" { "Sent" } },
+        Err(err) => html! { span class="has-text-danger" { (err.message) } },
+    }
+    .into_string()
+}
+
+pub async fn get_deliveries(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+) -> impl IntoResponse {
+    let deliveries = sqlx::query!(
+        r#"select d.created_at, c.name, d.payload, d.attempt, d.status, d.error, d.state
+            from notification_delivery d
+            join notification_channel c on c.id = d.channel_id
+            where c.user_id = $1
+            order by d.created_at desc
+            limit 50"#,
+        user_id
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+
+    let form = html! {
+        div class="box" {
+            h2 class="title" { "Notification Deliveries" }
+
+            table class="table is-size-7" style="width: 100%" {
+                thead {
+                    tr {
+                        th { "Time" }
+                        th { "Channel" }
+                        th { "Event" }
+                        th { "Attempt" }
+                        th { "Status" }
+                        th { "Result" }
+                    }
+                }
+                tbody {
+                    @for delivery in deliveries.iter() {
+                        @let event = serde_json::from_value::<Event>(delivery.payload.clone()).ok();
+                        @let color = match delivery.state.as_str() {
+                            "sent" => "has-text-primary",
+                            "failed" => "has-text-danger",
+                            "dropped" => "has-text-grey",
+                            _ => "has-text-warning-dark",
+                        };
+                        tr {
+                            td { (delivery.created_at.format("%H:%M:%S %d/%m/%Y")) }
+                            td { (delivery.name) }
+                            td { (event.map(|e| e.title()).unwrap_or_default()) }
+                            td { (delivery.attempt) }
+                            td { (delivery.status.map(|s| s.to_string()).unwrap_or_default()) }
+                            td class=(color) {
+                                (delivery.state)
+                                @if let Some(error) = &delivery.error {
+                                    br {}
+                                    span class="has-text-grey" { (error) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div style="width: 100%; display: flex; justify-content: flex-end;" {
+                button type="button" class="button is-danger is-light" hx-get=""
+                    hx-target="body" hx-swap="outherHTML"
+                    style="width:100px" {
+                    "Close"
+                }
+            }
+        }
+    };
+
+    (html! {
+        div class="modal is-active" id="modal" {
+            div class="modal-background" {}
+            div class="modal-content" style="width: 900px" {
+                (form)
+            }
+            button class="modal-close is-large" aria-label="close" {}
+        }
+    })
+    .into_string()
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_channels).post(create))
+        .route("/fields", get(get_fields))
+        .route("/deliveries", get(get_deliveries))
+        .route("/:id", delete(delete_channel))
+        .route("/:id/test", post(test))
+}
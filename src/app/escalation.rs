@@ -0,0 +1,255 @@
+use crate::{logged_user::LoggedUser, AppState};
+use axum::routing::{delete, get, post};
+use axum::{extract::*, response::*, Router};
+use maud::html;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn get_policies(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+) -> impl IntoResponse {
+    let policies = sqlx::query!(
+        r#"select id, name from escalation_policy where user_id = $1 order by name"#,
+        user_id
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+
+    let steps = sqlx::query!(
+        r#"select es.id, es.policy_id, es.delay_minutes, c.name as channel
+            from escalation_step es
+            join escalation_policy p on p.id = es.policy_id
+            join notification_channel c on c.id = es.channel_id
+            where p.user_id = $1
+            order by es.delay_minutes, es.id"#,
+        user_id
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+
+    let channels = sqlx::query!(
+        r#"select id, name from notification_channel where user_id = $1 order by name"#,
+        user_id
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+
+    let form = html! {
+        div class="box" {
+            h2 class="title" { "Escalation Policies" }
+            p class="subtitle is-size-6" {
+                "Each step notifies its channel once the monitor has been down for the delay, "
+                "until the incident is acknowledged or recovers."
+            }
+
+            @for policy in policies.iter() {
+                div class="mb-5" {
+                    div class="is-flex is-justify-content-space-between is-align-items-center" {
+                        h6 class="title is-size-5 m-0" { (policy.name) }
+                        button class="button is-danger is-inverted is-small"
+                            hx-delete={"/escalation/"(policy.id.to_string())}
+                            hx-confirm="Do you want to delete this policy?"
+                            hx-target="#modal" hx-swap="outerHTML" {
+                            i class="fa fa-trash" {}
+                        }
+                    }
+
+                    table class="table" style="width: 100%" {
+                        tbody {
+                            @for step in steps.iter().filter(|s| s.policy_id == policy.id) {
+                                tr {
+                                    td {
+                                        @if step.delay_minutes == 0 {
+                                            "Immediately"
+                                        } @else {
+                                            "After " (step.delay_minutes) " minutes"
+                                        }
+                                    }
+                                    td { (step.channel) }
+                                    td {
+                                        button class="button is-danger is-inverted is-small"
+                                            hx-delete={"/escalation/step/"(step.id.to_string())}
+                                            hx-target="#modal" hx-swap="outerHTML" {
+                                            i class="fa fa-trash" {}
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    form hx-post={"/escalation/"(policy.id.to_string())"/steps"}
+                        hx-target="#modal" hx-swap="outerHTML" {
+                        div class="field is-grouped" {
+                            div class="control" {
+                                input class="input" type="number" name="delay_minutes" min="0"
+                                    value="0" style="width: 90px" {}
+                            }
+                            div class="control flex1" {
+                                div class="select is-fullwidth" {
+                                    select name="channel_id" {
+                                        @for channel in channels.iter() {
+                                            option value=(channel.id.to_string()) { (channel.name) }
+                                        }
+                                    }
+                                }
+                            }
+                            div class="control" {
+                                button type="submit" class="button is-primary is-light" { "Add Step" }
+                            }
+                        }
+                    }
+                }
+            }
+
+            form hx-post="/escalation" hx-target="#modal" hx-swap="outerHTML" {
+                div class="field is-grouped" {
+                    div class="control flex1" {
+                        input class="input" name="name" placeholder="Policy name" required {}
+                    }
+                    div class="control" {
+                        button type="submit" class="button is-primary" { "Add" }
+                    }
+                }
+            }
+
+            div style="width: 100%; display: flex; justify-content: flex-end;" {
+                button class="button is-danger is-light" hx-get=""
+                    hx-target="body" hx-swap="outherHTML"
+                    style="width:100px" {
+                    "Close"
+                }
+            }
+        }
+    };
+
+    (html! {
+        div class="modal is-active" id="modal" {
+            div class="modal-background" {}
+            div class="modal-content" {
+                (form)
+            }
+            button class="modal-close is-large" aria-label="close" {}
+        }
+    })
+    .into_string()
+}
+
+#[derive(Deserialize)]
+pub struct PolicyParams {
+    pub name: String,
+}
+
+pub async fn create(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Form(params): Form<PolicyParams>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"insert into escalation_policy (user_id, name) values ($1, $2)"#,
+        user_id,
+        params.name.trim()
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    get_policies(State(db), LoggedUser(user_id)).await
+}
+
+pub async fn delete_policy(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let policy = sqlx::query!(
+        r#"select id from escalation_policy where id = $1 and user_id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    if policy.is_some() {
+        sqlx::query!(
+            r#"update website set escalation_policy_id = null where escalation_policy_id = $1"#,
+            id
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        sqlx::query!(r#"delete from escalation_step where policy_id = $1"#, id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        sqlx::query!(r#"delete from escalation_policy where id = $1"#, id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    get_policies(State(db), LoggedUser(user_id)).await
+}
+
+#[derive(Deserialize)]
+pub struct StepParams {
+    pub channel_id: Uuid,
+    pub delay_minutes: i32,
+}
+
+pub async fn add_step(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+    Form(params): Form<StepParams>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"insert into escalation_step (policy_id, channel_id, delay_minutes)
+            select p.id, c.id, $3 from escalation_policy p
+            join notification_channel c on c.user_id = p.user_id
+            where p.id = $1 and c.id = $2 and p.user_id = $4"#,
+        id,
+        params.channel_id,
+        params.delay_minutes.max(0),
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    get_policies(State(db), LoggedUser(user_id)).await
+}
+
+pub async fn delete_step(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"delete from escalation_step where id = $1
+            and policy_id in (select id from escalation_policy where user_id = $2)"#,
+        id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    get_policies(State(db), LoggedUser(user_id)).await
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_policies).post(create))
+        .route("/:id", delete(delete_policy))
+        .route("/:id/steps", post(add_step))
+        .route("/step/:id", delete(delete_step))
+}
@@ -1,5 +1,5 @@
 use super::layout;
-use crate::{logged_user::LoggedUser, AppState};
+use crate::{expiry, logged_user::LoggedUser, AppState};
 use axum::http::HeaderMap;
 use axum::routing::get;
 use axum::{extract::*, response::*, Router, http::StatusCode};
@@ -140,6 +140,10 @@ pub async fn home_get(
                         data-tooltip="Add Website" data-placement="bottom" {
                         span class="icon" { i class="fa fa-plus" {} }
                     }
+                    a class="button ml-1 is-black is-inverted" href="/incident"
+                        data-tooltip="Incidents" data-placement="bottom" {
+                        span class="icon" {i class="fa-solid fa-triangle-exclamation" {}}
+                    }
                     button class="button ml-1 is-black is-inverted" 
                         hx-get="/setting"  hx-swap="multi:#modal:outerHTML"                         data-tooltip="Setting" data-placement="bottom" {
                         span class="icon" {i class="fa-solid fa-gear" {}}
@@ -179,6 +183,8 @@ pub async fn home_get(
                         let state = match s.state.as_str() {
                             "up" => "has-background-primary",
                             "down" =>  "has-background-danger",
+                            "degraded" => "has-background-warning",
+                            "maintenance" => "has-background-info",
                             _ => "has-background-dark"
                         };
 
@@ -208,6 +214,15 @@ pub async fn home_get(
                         } else if ws.state == "down" {
                             "danger".to_string()
                         }
+                        else if ws.state == "degraded" {
+                            "warning".to_string()
+                        }
+                        else if ws.state == "maintenance" {
+                            "info".to_string()
+                        }
+                        else if ws.state == "dependency_down" {
+                            "danger".to_string()
+                        }
                         else {
                             "dark".to_string()
                         };
@@ -392,10 +407,54 @@ pub async fn get_setting(
             }
 
             div class="field" {
-                label class="label" {"Discord Webgook for Notification"}
+                label class="label" {"Expiry alerts (days before)"}
                 div class="control has-icons-left" {
-                    input class="input" placeholder="url" name="discord_webhook" value=(user.discord_webhook.unwrap_or("".to_string()))  {}
-                    span class="icon is-tags is-left" {i class="fa-solid fa-tags" {} }
+                    input class="input" placeholder=(expiry::DEFAULT_THRESHOLDS) name="expiry_thresholds"
+                    value=(user.expiry_thresholds) {}
+                    span class="icon is-small is-left" {i class="fa-solid fa-hourglass-half" {} }
+                }
+                p class="help" {"Domain and SSL certificate expiry alerts, each threshold is sent once."}
+            }
+
+            div class="field" {
+                a hx-get="/channel" hx-target="#modal" hx-swap="outerHTML" {
+                    span class="icon" {i class="fa-solid fa-bell" {} }
+                    "Manage notification channels"
+                }
+            }
+
+            div class="field" {
+                a hx-get="/channel/deliveries" hx-target="#modal" hx-swap="outerHTML" {
+                    span class="icon" {i class="fa-solid fa-list-check" {} }
+                    "Notification delivery log"
+                }
+            }
+
+            div class="field" {
+                a hx-get="/escalation" hx-target="#modal" hx-swap="outerHTML" {
+                    span class="icon" {i class="fa-solid fa-stairs" {} }
+                    "Manage escalation policies"
+                }
+            }
+
+            div class="field" {
+                a hx-get="/maintenance" hx-target="#modal" hx-swap="outerHTML" {
+                    span class="icon" {i class="fa-solid fa-screwdriver-wrench" {} }
+                    "Schedule maintenance windows"
+                }
+            }
+
+            div class="field" {
+                a hx-get="/status-page" hx-target="#modal" hx-swap="outerHTML" {
+                    span class="icon" {i class="fa-solid fa-signal" {} }
+                    "Configure the public status page"
+                }
+            }
+
+            div class="field" {
+                a hx-get="/secret" hx-target="#modal" hx-swap="outerHTML" {
+                    span class="icon" {i class="fa-solid fa-key" {} }
+                    "Manage secrets for monitor headers"
                 }
             }
 
@@ -437,23 +496,24 @@ pub async fn get_setting(
 pub struct UpdateSettingParam {
     pub name: String,
     pub email: String,
-    pub discord_webhook: String,
+    pub expiry_thresholds: String,
 }
 pub async fn post_setting(
     State(client): State<PgPool>,
     LoggedUser(user_id): LoggedUser,
     Form(params): Form<UpdateSettingParam>,
 ) -> impl IntoResponse {
-    let wb = if params.discord_webhook.is_empty() {
-        None
-    } else {
-        Some(params.discord_webhook)
-    };
+    let expiry_thresholds = expiry::thresholds(&params.expiry_thresholds)
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+
     sqlx::query!(
-        r#"update "user" set name=$3, discord_webhook=$2 where id = $1"#,
+        r#"update "user" set name=$2, expiry_thresholds=$3 where id = $1"#,
         user_id,
-        wb,
-        params.name
+        params.name,
+        expiry_thresholds
     )
     .execute(&client)
     .await
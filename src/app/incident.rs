@@ -0,0 +1,539 @@
+use crate::notification::format_duration;
+use crate::{incident, logged_user::LoggedUser, AppState};
+use axum::http::HeaderMap;
+use axum::routing::{get, post};
+use axum::{extract::*, http::StatusCode, response::*, Router};
+use chrono::Utc;
+use maud::{html, Markup};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::layout;
+
+fn header() -> Markup {
+    html! {
+        header style="display: flex; justify-content: space-between; align-items:center" {
+            a class="up" href="/" hx-boost="true" hx-target="body" hx-swap="outerHTML" {
+                img src="/logofull.png" style="height:60px" {}
+            }
+
+            div style="display:flex;" {
+                a class="button is-dark is-inverted ml-1" href="/incident"
+                    data-tooltip="Incidents" data-placement="bottom" {
+                    span class="icon"{ i class="fa-solid fa-triangle-exclamation" {} }
+                }
+                button class="button is-danger is-inverted ml-1"
+                    hx-post="/auth/logout" hx-target="body" hx-push-url="true"
+                    data-tooltip="Logout" data-placement="bottom" {
+                    span class="icon"{ i class="fa-solid fa-right-from-bracket" {} }
+                }
+            }
+        }
+    }
+}
+
+fn duration(seconds: Option<i64>) -> String {
+    seconds.map(format_duration).unwrap_or("-".to_string())
+}
+
+pub async fn get_incidents(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+) -> impl IntoResponse {
+    let incidents = sqlx::query!(
+        r#"select i.id, i.opened_at, i.resolved_at, i.acknowledged_by, i.assignee, ws.url
+        from incident i
+        join website ws on ws.id = i.website_id
+        where ws.user_id = $1
+        order by i.opened_at desc
+        limit 100"#,
+        user_id
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+
+    // mean time to acknowledge and to recover over the last 30 days
+    let summary = sqlx::query!(
+        r#"select count(*) as "count!",
+            avg(extract(epoch from i.resolved_at - i.opened_at))::bigint as mttr,
+            avg(extract(epoch from i.acknowledged_at - i.opened_at))::bigint as mtta
+        from incident i
+        join website ws on ws.id = i.website_id
+        where ws.user_id = $1 and i.opened_at > now() - interval '30 days'"#,
+        user_id
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    let per_website = sqlx::query!(
+        r#"select ws.url, count(*) as "count!",
+            avg(extract(epoch from i.resolved_at - i.opened_at))::bigint as mttr
+        from incident i
+        join website ws on ws.id = i.website_id
+        where ws.user_id = $1 and i.opened_at > now() - interval '30 days'
+        group by ws.url
+        order by mttr desc nulls last"#,
+        user_id
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+
+    let now = Utc::now();
+
+    layout::page(html!{}, html! {
+        div class="has-background-white p-5" style="min-height: 100vh" {
+            div class="container is-flex is-flex-direction-column" {
+                (header())
+
+                h4 class="title is-size-4 mt-4" { "Incidents" }
+
+                div class="columns" {
+                    div class="column" {
+                        div class="box has-text-centered" {
+                            p class="heading" { "Incidents (30 days)" }
+                            p class="title" { (summary.count) }
+                        }
+                    }
+                    div class="column" {
+                        div class="box has-text-centered" {
+                            p class="heading" { "MTTA" }
+                            p class="title" { (duration(summary.mtta)) }
+                        }
+                    }
+                    div class="column" {
+                        div class="box has-text-centered" {
+                            p class="heading" { "MTTR" }
+                            p class="title" { (duration(summary.mttr)) }
+                        }
+                    }
+                }
+
+                div class="is-flex" {
+                    div class="box flex1 mr-2" {
+                        table class="table" style="width: 100%" {
+                            thead {
+                                tr {
+                                    th { "Website" }
+                                    th { "Opened" }
+                                    th { "Duration" }
+                                    th { "Assignee" }
+                                    th { "Status" }
+                                }
+                            }
+                            tbody {
+                                @for i in incidents.iter() {
+                                    tr {
+                                        td { a href={"/incident/"(i.id.to_string())} { (i.url) } }
+                                        td { (i.opened_at.format("%H:%M %d/%m/%Y").to_string()) }
+                                        td {
+                                            (format_duration((i.resolved_at.unwrap_or(now) - i.opened_at).num_seconds()))
+                                        }
+                                        td { (i.assignee.clone().unwrap_or_default()) }
+                                        td {
+                                            @if i.resolved_at.is_some() {
+                                                span class="tag is-primary" { "resolved" }
+                                            } @else if i.acknowledged_by.is_some() {
+                                                span class="tag is-warning" { "acknowledged" }
+                                            } @else {
+                                                span class="tag is-danger" { "open" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        @if incidents.is_empty() {
+                            p class="subtitle is-size-6" { "No incidents yet" }
+                        }
+                    }
+
+                    div class="box" style="min-width: 300px" {
+                        h6 class="title is-size-5" { "MTTR by website" }
+                        table class="table" style="width: 100%" {
+                            tbody {
+                                @for ws in per_website.iter() {
+                                    tr {
+                                        td style="word-break: break-all" { (ws.url) }
+                                        td { (ws.count) }
+                                        td { (duration(ws.mttr)) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+async fn timeline(db: &PgPool, id: Uuid) -> Markup {
+    let events = sqlx::query!(
+        r#"select kind, message, author, created_at from incident_event
+        where incident_id = $1
+        order by created_at"#,
+        id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    html! { div id="timeline" {
+        @for event in events.iter() {
+            @let color = match event.kind.as_str() {
+                "opened" => "is-danger",
+                "resolved" => "is-primary",
+                "acknowledged" => "is-warning",
+                "comment" => "is-info",
+                _ => "is-light",
+            };
+            article class="media" {
+                div class="media-left" style="width: 140px" {
+                    p class="is-size-7" { (event.created_at.format("%H:%M:%S %d/%m/%Y").to_string()) }
+                    span class={"tag " (color)} { (event.kind) }
+                }
+                div class="media-content" {
+                    @if let Some(author) = &event.author {
+                        p class="is-size-7 has-text-grey" { (author) }
+                    }
+                    p style="white-space: pre-wrap; word-break: break-word" { (event.message) }
+                }
+            }
+        }
+
+        form class="mt-4" hx-post={"/incident/"(id.to_string())"/comments"}
+            hx-target="#timeline" hx-swap="outerHTML" {
+            div class="field" {
+                div class="control" {
+                    textarea class="textarea" rows="2" name="message" placeholder="Add a comment" required {}
+                }
+            }
+            button type="submit" class="button is-primary is-light" { "Comment" }
+        }
+    }}
+}
+
+pub async fn get_incident(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let incident = sqlx::query!(
+        r#"select i.*, ws.url from incident i
+        join website ws on ws.id = i.website_id
+        where i.id = $1 and ws.user_id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let Some(incident) = incident else {
+        return Redirect::to("/incident").into_response();
+    };
+
+    let elapsed = (incident.resolved_at.unwrap_or(Utc::now()) - incident.opened_at).num_seconds();
+    let timeline_panel = timeline(&db, id).await;
+
+    layout::page(html!{}, html! {
+        div class="has-background-white p-5" style="min-height: 100vh" {
+            div class="container is-flex is-flex-direction-column" {
+                (header())
+
+                div class="is-flex mt-4 is-justify-content-space-between is-align-items-center" {
+                    h4 class="title m-0 is-size-4" {
+                        a href={"/website/"(incident.website_id.to_string())} { (incident.url) }
+                        @if incident.resolved_at.is_some() {
+                            span class="tag is-primary ml-2" { "resolved" }
+                        } @else {
+                            span class="tag is-danger ml-2" { "open" }
+                        }
+                    }
+                    @if incident.resolved_at.is_none() && incident.acknowledged_at.is_none() {
+                        button class="button is-danger"
+                            hx-post={"/incident/"(id.to_string())"/ack"} {
+                            "Acknowledge"
+                        }
+                    }
+                }
+
+                div class="is-flex mt-2" {
+                    div class="flex1 mr-2" {
+                        div class="box" {
+                            table class="table" style="width: 100%" {
+                                tbody {
+                                    tr { td { "Opened" } td { (incident.opened_at.format("%H:%M %d/%m/%Y").to_string()) } }
+                                    tr {
+                                        td { "Resolved" }
+                                        td { (incident.resolved_at.map(|r| r.format("%H:%M %d/%m/%Y").to_string()).unwrap_or("-".to_string())) }
+                                    }
+                                    tr { td { "Duration" } td { (format_duration(elapsed)) } }
+                                    tr {
+                                        td { "Acknowledged" }
+                                        td {
+                                            @if let Some(at) = incident.acknowledged_at {
+                                                (incident.acknowledged_by.clone().unwrap_or_default())
+                                                " at " (at.format("%H:%M %d/%m/%Y").to_string())
+                                            } @else {
+                                                "-"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
+                        form class="box" hx-post={"/incident/"(id.to_string())} hx-target="#saved" {
+                            div class="field" {
+                                label class="label" { "Assignee" }
+                                div class="control has-icons-left" {
+                                    input class="input" name="assignee" placeholder="Who is on it"
+                                        value=(incident.assignee.clone().unwrap_or_default()) {}
+                                    span class="icon is-small is-left" {i class="fa-solid fa-user" {} }
+                                }
+                            }
+                            div class="field" {
+                                label class="label" { "Root Cause" }
+                                div class="control" {
+                                    textarea class="textarea" rows="4" name="root_cause"
+                                        placeholder="What happened and why" {
+                                        (incident.root_cause.clone().unwrap_or_default())
+                                    }
+                                }
+                            }
+                            button type="submit" class="button is-primary" { "Save" }
+                            span class="ml-2 has-text-primary" id="saved" {}
+                        }
+                    }
+
+                    div class="box flex1" {
+                        h6 class="title is-size-5" { "Timeline" }
+                        (timeline_panel)
+                    }
+                }
+            }
+        }
+    }).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct IncidentParams {
+    pub assignee: String,
+    pub root_cause: String,
+}
+
+fn optional(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+pub async fn update(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+    Form(params): Form<IncidentParams>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"update incident set assignee = $3, root_cause = $4
+        where id = $1 and website_id in (select id from website where user_id = $2)"#,
+        id,
+        user_id,
+        optional(&params.assignee),
+        optional(&params.root_cause)
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    "Saved"
+}
+
+#[derive(Deserialize)]
+pub struct CommentParams {
+    pub message: String,
+}
+
+pub async fn comment(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+    Form(params): Form<CommentParams>,
+) -> impl IntoResponse {
+    let user = sqlx::query!(
+        r#"select u.email from "user" u
+        join website ws on ws.user_id = u.id
+        join incident i on i.website_id = ws.id
+        where i.id = $1 and u.id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let Some(user) = user else {
+        return (StatusCode::NOT_FOUND, "Incident not found").into_response();
+    };
+
+    if let Some(message) = optional(&params.message) {
+        incident::record(&db, id, "comment", &message, Some(&user.email)).await;
+    }
+
+    timeline(&db, id).await.into_string().into_response()
+}
+
+pub async fn acknowledge(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let user = sqlx::query!(
+        r#"select u.email, i.website_id from "user" u
+        join website ws on ws.user_id = u.id
+        join incident i on i.website_id = ws.id
+        where i.id = $1 and u.id = $2 and i.resolved_at is null"#,
+        id,
+        user_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    if let Some(user) = user {
+        incident::acknowledge(&db, user.website_id, &user.email).await;
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert("hx-refresh", "true".parse().unwrap());
+    headers.into_response()
+}
+
+fn ack_page(title: &str, body: Markup) -> Html<String> {
+    layout::page(
+        html! {},
+        html! {
+            app style="width: 100vw; height: 100vh; display: flex; flex-direction:column;
+                justify-content: center; align-items: center" {
+                h5 class="title is-size-4" { (title) }
+                (body)
+            }
+        },
+    )
+}
+
+fn invalid_ack_link() -> Html<String> {
+    ack_page(
+        "This acknowledge link is invalid or has expired",
+        html! { p class="subtitle is-size-5" { "Open AllGreen to acknowledge the incident" } },
+    )
+}
+
+// the link only shows a confirmation, so link previews and mail scanners can't acknowledge
+pub async fn get_ack_link(
+    State(db): State<PgPool>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    let Some((incident_id, _)) = incident::verify_ack_token(&token) else {
+        return invalid_ack_link();
+    };
+
+    let incident = sqlx::query!(
+        r#"select ws.url, i.resolved_at, i.acknowledged_by from incident i
+        join website ws on ws.id = i.website_id
+        where i.id = $1"#,
+        incident_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let Some(incident) = incident else {
+        return invalid_ack_link();
+    };
+
+    if incident.resolved_at.is_some() {
+        return ack_page(
+            &format!("The incident of {} is already resolved", incident.url),
+            html! { p class="subtitle is-size-5" { "You can close this window" } },
+        );
+    }
+    if let Some(by) = incident.acknowledged_by {
+        return ack_page(
+            &format!("The incident of {} is acknowledged by {}", incident.url, by),
+            html! { p class="subtitle is-size-5" { "You can close this window" } },
+        );
+    }
+
+    ack_page(
+        &format!("Acknowledge the incident of {}", incident.url),
+        html! {
+            form method="post" style="width: 300px" {
+                div class="field" {
+                    div class="control" {
+                        input class="input" name="name" placeholder="Your name (optional)" {}
+                    }
+                }
+                button type="submit" class="button is-danger is-fullwidth" { "Acknowledge" }
+            }
+        },
+    )
+}
+
+#[derive(Deserialize)]
+pub struct AckParams {
+    pub name: String,
+}
+
+pub async fn post_ack_link(
+    State(db): State<PgPool>,
+    Path(token): Path<String>,
+    Form(params): Form<AckParams>,
+) -> impl IntoResponse {
+    let Some((incident_id, channel_id)) = incident::verify_ack_token(&token) else {
+        return invalid_ack_link();
+    };
+
+    let channel = sqlx::query!(
+        r#"select name from notification_channel where id = $1"#,
+        channel_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+    let channel = channel.map(|c| c.name).unwrap_or("alert".to_string());
+
+    let by = match optional(&params.name) {
+        Some(name) => format!("{} via {}", name, channel),
+        None => format!("{} link", channel),
+    };
+
+    if incident::acknowledge_incident(&db, incident_id, &by).await {
+        ack_page(
+            "Incident acknowledged",
+            html! { p class="subtitle is-size-5" { "Escalation and reminders are stopped" } },
+        )
+    } else {
+        ack_page(
+            "The incident is already acknowledged or resolved",
+            html! { p class="subtitle is-size-5" { "You can close this window" } },
+        )
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_incidents))
+        .route("/:id", get(get_incident).post(update))
+        .route("/:id/comments", post(comment))
+        .route("/:id/ack", post(acknowledge))
+        .route("/ack/:token", get(get_ack_link).post(post_ack_link))
+}
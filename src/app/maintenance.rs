@@ -0,0 +1,220 @@
+use crate::{logged_user::LoggedUser, maintenance, AppState};
+use axum::routing::{delete, get};
+use axum::{extract::*, response::*, Router};
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use maud::html;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn windows_modal(db: &PgPool, user_id: Uuid, error: Option<String>) -> String {
+    let windows = sqlx::query!(
+        r#"select mw.id, mw.name, mw.tag, mw.starts_at, mw.duration_minutes, mw.rrule,
+            ws.url as "url?"
+        from maintenance_window mw
+        left join website ws on ws.id = mw.website_id
+        where mw.user_id = $1
+        order by mw.starts_at desc"#,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let websites = sqlx::query!(
+        r#"select id, url from website where user_id = $1 order by url"#,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let now = Utc::now();
+
+    let form = html! {
+        div class="box" {
+            h2 class="title" { "Maintenance Windows" }
+            p class="subtitle is-size-6" {
+                "Checks during a window are recorded as maintenance, they don't alert and don't count against uptime. "
+                "Times are in UTC."
+            }
+
+            table class="table is-size-7" style="width: 100%" {
+                tbody {
+                    @for window in windows.iter() {
+                        tr {
+                            td {
+                                (window.name)
+                                @if maintenance::is_active(window.starts_at, window.duration_minutes, window.rrule.as_deref(), now) {
+                                    span class="tag is-info ml-1" { "active" }
+                                }
+                            }
+                            td style="word-break: break-all" {
+                                @if let Some(url) = &window.url {
+                                    (url)
+                                } @else if let Some(tag) = &window.tag {
+                                    span class="tag" { (tag) }
+                                }
+                            }
+                            td {
+                                (window.starts_at.format("%H:%M %d/%m/%Y").to_string())
+                                " for " (window.duration_minutes) "m"
+                            }
+                            td { code { (window.rrule.clone().unwrap_or("once".to_string())) } }
+                            td {
+                                button class="button is-danger is-inverted is-small"
+                                    hx-delete={"/maintenance/"(window.id.to_string())}
+                                    hx-confirm="Do you want to delete this window?"
+                                    hx-target="#modal" hx-swap="outerHTML" {
+                                    i class="fa fa-trash" {}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            form hx-post="/maintenance" hx-target="#modal" hx-swap="outerHTML" {
+                div class="field is-grouped" {
+                    div class="control flex1" {
+                        input class="input" name="name" placeholder="Name" required {}
+                    }
+                    div class="control flex1" {
+                        div class="select is-fullwidth" {
+                            select name="website_id" {
+                                option value="" { "Websites with the tag" }
+                                @for website in websites.iter() {
+                                    option value=(website.id.to_string()) { (website.url) }
+                                }
+                            }
+                        }
+                    }
+                    div class="control" {
+                        input class="input" name="tag" placeholder="Tag" style="width: 120px" {}
+                    }
+                }
+                div class="field is-grouped" {
+                    div class="control" {
+                        input class="input" type="datetime-local" name="starts_at" required {}
+                    }
+                    div class="control" {
+                        input class="input" type="number" min="1" name="duration_minutes"
+                            placeholder="Minutes" required style="width: 110px" {}
+                    }
+                    div class="control flex1" {
+                        input class="input" name="rrule" placeholder="FREQ=WEEKLY;BYDAY=SU (empty for once)" {}
+                    }
+                }
+                p class="help mb-3" {
+                    "Repeats support FREQ=DAILY, WEEKLY or MONTHLY with INTERVAL, BYDAY=MO,TU,.. and UNTIL=20261231."
+                }
+                @if let Some(error) = &error {
+                    p class="has-text-danger mb-3" { (error) }
+                }
+                button type="submit" class="button is-primary" { "Add" }
+            }
+
+            div style="width: 100%; display: flex; justify-content: flex-end;" {
+                button class="button is-danger is-light" hx-get=""
+                    hx-target="body" hx-swap="outherHTML"
+                    style="width:100px" {
+                    "Close"
+                }
+            }
+        }
+    };
+
+    (html! {
+        div class="modal is-active" id="modal" {
+            div class="modal-background" {}
+            div class="modal-content" style="width: 800px" {
+                (form)
+            }
+            button class="modal-close is-large" aria-label="close" {}
+        }
+    })
+    .into_string()
+}
+
+pub async fn get_windows(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+) -> impl IntoResponse {
+    windows_modal(&db, user_id, None).await
+}
+
+#[derive(Deserialize)]
+pub struct WindowParams {
+    pub name: String,
+    pub website_id: String,
+    pub tag: String,
+    pub starts_at: String,
+    pub duration_minutes: i32,
+    pub rrule: String,
+}
+
+pub async fn create(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Form(params): Form<WindowParams>,
+) -> impl IntoResponse {
+    let website_id = params.website_id.parse::<Uuid>().ok();
+    let tag = Some(params.tag.trim().to_string()).filter(|t| !t.is_empty());
+    let rrule = Some(params.rrule.trim().to_uppercase()).filter(|r| !r.is_empty());
+    let starts_at = NaiveDateTime::parse_from_str(&params.starts_at, "%Y-%m-%dT%H:%M")
+        .map(|d| Utc.from_utc_datetime(&d));
+
+    let error = if website_id.is_none() && tag.is_none() {
+        Some("Choose a website or a tag".to_string())
+    } else if starts_at.is_err() {
+        Some("Invalid start time".to_string())
+    } else if params.duration_minutes <= 0 {
+        Some("Duration must be positive".to_string())
+    } else {
+        rrule.as_deref().and_then(|r| maintenance::parse(r).err())
+    };
+
+    if let (None, Ok(starts_at)) = (&error, starts_at) {
+        sqlx::query!(
+            r#"insert into maintenance_window
+                (user_id, name, website_id, tag, starts_at, duration_minutes, rrule)
+            select $1, $2, $3, $4, $5, $6, $7
+            where $3::uuid is null or exists (select 1 from website where id = $3 and user_id = $1)"#,
+            user_id,
+            params.name.trim(),
+            website_id,
+            if website_id.is_some() { None } else { tag },
+            starts_at,
+            params.duration_minutes,
+            rrule
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+    }
+
+    windows_modal(&db, user_id, error).await
+}
+
+pub async fn delete_window(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"delete from maintenance_window where id = $1 and user_id = $2"#,
+        id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    windows_modal(&db, user_id, None).await
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_windows).post(create))
+        .route("/:id", delete(delete_window))
+}
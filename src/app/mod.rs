@@ -2,15 +2,31 @@ use axum::Router;
 use crate::AppState;
 
 mod logo;
+mod announcement;
 mod auth;
+mod badge;
+mod channel;
+mod escalation;
 mod home;
+mod incident;
 mod layout;
+mod maintenance;
 mod website;
-mod mail;
+pub mod mail;
+mod secret;
+pub mod status_page;
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .nest("/", home::router())
         .nest("/auth",  auth::router())
         .nest("/website", website::router())
+        .nest("/secret", secret::router())
+        .nest("/channel", channel::router())
+        .nest("/escalation", escalation::router())
+        .nest("/incident", incident::router())
+        .nest("/maintenance", maintenance::router())
+        .nest("/status-page", status_page::router())
+        .nest("/status", status_page::public_router())
+        .nest("/badge", badge::router())
 }
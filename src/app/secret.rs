@@ -0,0 +1,134 @@
+use crate::{crypto, logged_user::LoggedUser, AppState};
+use axum::routing::{delete, get};
+use axum::{extract::*, response::*, Router};
+use maud::html;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn get_secrets(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+) -> impl IntoResponse {
+    let secrets = sqlx::query!(
+        r#"select id, name, created_at from secret where user_id = $1 order by name"#,
+        user_id
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+
+    let form = html! {
+        div class="box" {
+            h2 class="title" { "Secrets" }
+            p class="subtitle is-size-6" {
+                "Secrets are stored encrypted and never shown again. Use them in monitor headers as "
+                code {"{{secret:NAME}}"}
+            }
+
+            table class="table" style="width: 100%" {
+                tbody {
+                    @for secret in secrets.iter() {
+                        tr {
+                            td { code { (secret.name) } }
+                            td { (secret.created_at.format("%d/%m/%Y").to_string()) }
+                            td {
+                                button class="button is-danger is-inverted is-small"
+                                    hx-delete={"/secret/"(secret.id.to_string())}
+                                    hx-confirm="Do you want to delete this secret?"
+                                    hx-target="#modal" hx-swap="outerHTML" {
+                                    i class="fa fa-trash" {}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            form hx-post="/secret" hx-target="#modal" hx-swap="outerHTML" {
+                div class="field is-grouped" {
+                    div class="control has-icons-left flex1" {
+                        input class="input" name="name" placeholder="NAME" required
+                            pattern="[A-Za-z0-9_]+" {}
+                        span class="icon is-small is-left" {i class="fa-solid fa-tag" {} }
+                    }
+                    div class="control has-icons-left flex1" {
+                        input class="input" type="password" name="value" placeholder="value" required
+                            autocomplete="new-password" {}
+                        span class="icon is-small is-left" {i class="fa-solid fa-key" {} }
+                    }
+                    div class="control" {
+                        button type="submit" class="button is-primary" { "Add" }
+                    }
+                }
+            }
+
+            div style="width: 100%; display: flex; justify-content: flex-end;" {
+                button class="button is-danger is-light" hx-get=""
+                    hx-target="body" hx-swap="outherHTML"
+                    style="width:100px" {
+                    "Close"
+                }
+            }
+        }
+    };
+
+    (html! {
+        div class="modal is-active" id="modal" {
+            div class="modal-background" {}
+            div class="modal-content" {
+                (form)
+            }
+            button class="modal-close is-large" aria-label="close" {}
+        }
+    })
+    .into_string()
+}
+
+#[derive(Deserialize)]
+pub struct SecretParams {
+    pub name: String,
+    pub value: String,
+}
+
+pub async fn create(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Form(params): Form<SecretParams>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"insert into secret (user_id, name, value) values ($1, $2, $3)
+            on conflict (user_id, name) do update set value = excluded.value"#,
+        user_id,
+        params.name.trim(),
+        crypto::encrypt(&params.value)
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    get_secrets(State(db), LoggedUser(user_id)).await
+}
+
+pub async fn delete_secret(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"delete from secret where id = $1 and user_id = $2"#,
+        id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    get_secrets(State(db), LoggedUser(user_id)).await
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_secrets).post(create))
+        .route("/:id", delete(delete_secret))
+}
@@ -0,0 +1,844 @@
+use crate::{appconfig::ENV, crypto, logged_user::LoggedUser, uptime, AppState};
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::middleware::Next;
+use axum::routing::{delete, get, post};
+use axum::{extract::*, response::*, Router};
+use chrono::{DateTime, Duration, Utc};
+use execute::Execute;
+use maud::{html, Markup};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::process::{Command, Stdio};
+use uuid::Uuid;
+
+use super::{announcement, layout};
+
+pub const UPTIME_DAYS: i64 = 90;
+pub const INCIDENT_DAYS: i64 = 14;
+
+pub struct StatusPage {
+    pub id: Uuid,
+    pub slug: String,
+    pub title: String,
+    pub logo_url: Option<String>,
+    pub brand_color: String,
+    pub hide_urls: bool,
+    pub custom_domain: Option<String>,
+    pub domain_verified_at: Option<DateTime<Utc>>,
+}
+
+// the verified custom domain when there is one
+pub fn page_url(page: &StatusPage) -> String {
+    match (&page.custom_domain, page.domain_verified_at) {
+        (Some(domain), Some(_)) => format!("https://{}", domain),
+        _ => format!("{}/status/{}", ENV.host, page.slug),
+    }
+}
+
+fn state_label(state: &str) -> (&'static str, &'static str) {
+    match state {
+        "up" => ("Operational", "#48c78e"),
+        "degraded" => ("Degraded performance", "#ffb70f"),
+        "down" | "dependency_down" => ("Outage", "#f14668"),
+        "maintenance" => ("Maintenance", "#3e8ed0"),
+        _ => ("Unknown", "#b5b5b5"),
+    }
+}
+
+fn bar_color(ratio: Option<f64>) -> &'static str {
+    match ratio {
+        None => "#dbdbdb",
+        Some(r) if r >= 0.999 => "#48c78e",
+        Some(r) if r >= 0.99 => "#ffb70f",
+        Some(_) => "#f14668",
+    }
+}
+
+pub async fn render(db: &PgPool, page: StatusPage) -> Response {
+    let monitors = sqlx::query!(
+        r#"select m.website_id, m.display_name, m.section_id, ws.url, last.state as "state?"
+        from status_page_monitor m
+        join website ws on ws.id = m.website_id
+        left join lateral (
+            select state from website_state
+            where website_id = ws.id
+            order by created_at desc limit 1
+        ) as last on true
+        where m.status_page_id = $1
+        order by coalesce(m.display_name, ws.url)"#,
+        page.id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let sections = sqlx::query!(
+        r#"select id, name from status_page_section
+        where status_page_id = $1
+        order by position, name"#,
+        page.id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let incidents = sqlx::query!(
+        r#"select i.opened_at, i.resolved_at, m.display_name, ws.url
+        from incident i
+        join status_page_monitor m on m.website_id = i.website_id and m.status_page_id = $1
+        join website ws on ws.id = i.website_id
+        where i.opened_at > $2
+        order by i.opened_at desc
+        limit 20"#,
+        page.id,
+        Utc::now() - Duration::days(INCIDENT_DAYS)
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let ids: Vec<Uuid> = monitors.iter().map(|m| m.website_id).collect();
+    let spans = uptime::spans(db, &ids, Utc::now() - Duration::days(UPTIME_DAYS)).await;
+
+    let name = |display_name: &Option<String>, url: &str| -> String {
+        match display_name {
+            Some(name) => name.clone(),
+            None if page.hide_urls => "Service".to_string(),
+            None => url.to_string(),
+        }
+    };
+
+    let states: Vec<&str> = monitors
+        .iter()
+        .map(|m| m.state.as_deref().unwrap_or(""))
+        .collect();
+    let (overall, overall_color) = if states
+        .iter()
+        .any(|s| *s == "down" || *s == "dependency_down")
+    {
+        ("Major outage", "#f14668")
+    } else if states.contains(&"degraded") {
+        ("Partial outage", "#ffb70f")
+    } else if states.contains(&"maintenance") {
+        ("Under maintenance", "#3e8ed0")
+    } else {
+        ("All systems operational", page.brand_color.as_str())
+    };
+
+    let mut groups: Vec<(String, Option<Uuid>)> = sections
+        .iter()
+        .map(|s| (s.name.clone(), Some(s.id)))
+        .collect();
+    if monitors.iter().any(|m| m.section_id.is_none()) {
+        groups.insert(0, ("Services".to_string(), None));
+    }
+
+    let announcements = announcement::panel(db, &page, INCIDENT_DAYS).await;
+
+    let empty = vec![];
+    let content = html! {
+        div class="container p-5" style="max-width: 860px" {
+            header class="is-flex is-align-items-center mb-5" {
+                @if let Some(logo_url) = &page.logo_url {
+                    img src=(logo_url) style="max-height: 50px" class="mr-3" {}
+                }
+                h1 class="title m-0" { (page.title) }
+            }
+
+            div class="notification has-text-white has-text-weight-bold is-size-5"
+                style={"background-color: " (overall_color)} {
+                (overall)
+            }
+
+            (announcements)
+
+            @for (section, section_id) in groups.iter() {
+                @let section_monitors: Vec<_> = monitors.iter().filter(|m| m.section_id == *section_id).collect();
+                @if !section_monitors.is_empty() {
+                    div class="box" {
+                        h2 class="title is-size-5" { (section) }
+                        @for monitor in section_monitors {
+                            @let (label, color) = state_label(monitor.state.as_deref().unwrap_or(""));
+                            @let monitor_spans = spans.get(&monitor.website_id).unwrap_or(&empty);
+                            @let from = Utc::now() - Duration::days(UPTIME_DAYS);
+                            div class="mb-4" {
+                                div class="is-flex is-justify-content-space-between" {
+                                    span class="has-text-weight-semibold" { (name(&monitor.display_name, &monitor.url)) }
+                                    span style={"color: " (color)} { (label) }
+                                }
+                                div class="is-flex mt-1" style="gap: 2px" {
+                                    @for (day, ratio) in uptime::daily(monitor_spans, UPTIME_DAYS) {
+                                        div class="flex1" style={"height: 30px; border-radius: 2px; background-color: " (bar_color(ratio))}
+                                            aria-label={(day.format("%d/%m/%Y")) " " (uptime::percent(ratio))}
+                                            data-cooltipz-dir="top" {}
+                                    }
+                                }
+                                div class="is-flex is-justify-content-space-between is-size-7 has-text-grey" {
+                                    span { (UPTIME_DAYS) " days ago" }
+                                    span { (uptime::percent(uptime::ratio(monitor_spans, from, Utc::now()))) " uptime" }
+                                    span { "Today" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div class="box" {
+                h2 class="title is-size-5" { "Recent Incidents" }
+                @if incidents.is_empty() {
+                    p class="has-text-grey" { "No incidents in the last " (INCIDENT_DAYS) " days" }
+                }
+                @for incident in incidents.iter() {
+                    div class="mb-3" {
+                        p class="has-text-weight-semibold" { (name(&incident.display_name, &incident.url)) }
+                        p class="is-size-7 has-text-grey" {
+                            (incident.opened_at.format("%H:%M %d/%m/%Y UTC").to_string())
+                            @if let Some(resolved_at) = incident.resolved_at {
+                                " - resolved at " (resolved_at.format("%H:%M %d/%m/%Y UTC").to_string())
+                            } @else {
+                                " - ongoing"
+                            }
+                        }
+                    }
+                }
+            }
+
+            (announcement::subscribe_box(&page))
+
+            p class="has-text-centered is-size-7 has-text-grey" {
+                "Powered by " a href="https://allgreen.me" { "AllGreen" }
+            }
+        }
+    };
+
+    layout::page(
+        html! {
+            meta http-equiv="refresh" content="60";
+        },
+        html! {
+            div style="min-height: 100vh" class="has-background-light" {
+                (content)
+            }
+        },
+    )
+    .into_response()
+}
+
+pub async fn get_status_page(
+    State(db): State<PgPool>,
+    Path(slug): Path<String>,
+) -> impl IntoResponse {
+    let page = sqlx::query_as!(
+        StatusPage,
+        r#"select id, slug, title, logo_url, brand_color, hide_urls,
+            custom_domain, domain_verified_at
+        from status_page where slug = $1"#,
+        slug
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    match page {
+        Some(page) => render(&db, page).await,
+        None => (StatusCode::NOT_FOUND, "Status page not found").into_response(),
+    }
+}
+
+async fn settings_modal(db: &PgPool, user_id: Uuid, error: Option<&str>) -> String {
+    let page = sqlx::query_as!(
+        StatusPage,
+        r#"select id, slug, title, logo_url, brand_color, hide_urls,
+            custom_domain, domain_verified_at
+        from status_page where user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap();
+
+    let page_form = html! {
+        form hx-post="/status-page" hx-target="#modal" hx-swap="outerHTML" {
+            div class="field is-grouped" {
+                div class="control flex1" {
+                    label class="label" { "Title" }
+                    input class="input" name="title" placeholder="Acme Status" required
+                        value=(page.as_ref().map(|p| p.title.clone()).unwrap_or_default()) {}
+                }
+                div class="control flex1" {
+                    label class="label" { "Address" }
+                    input class="input" name="slug" placeholder="acme" required pattern="[a-z0-9-]+"
+                        value=(page.as_ref().map(|p| p.slug.clone()).unwrap_or_default()) {}
+                }
+            }
+            div class="field is-grouped" {
+                div class="control flex1" {
+                    label class="label" { "Logo Url" }
+                    input class="input" type="url" name="logo_url" placeholder="https://example.com/logo.png"
+                        value=(page.as_ref().and_then(|p| p.logo_url.clone()).unwrap_or_default()) {}
+                }
+                div class="control" {
+                    label class="label" { "Colour" }
+                    input class="input" type="color" name="brand_color" style="width: 80px"
+                        value=(page.as_ref().map(|p| p.brand_color.clone()).unwrap_or("#00d1b2".to_string())) {}
+                }
+            }
+            div class="field" {
+                label class="checkbox" {
+                    input type="checkbox" name="hide_urls" value="true"
+                        checked[page.as_ref().map_or(false, |p| p.hide_urls)] {}
+                    " Hide website urls, monitors without a display name are shown as Service"
+                }
+            }
+            @if let Some(error) = error {
+                p class="has-text-danger mb-3" { (error) }
+            }
+            div class="is-flex is-align-items-center" {
+                button type="submit" class="button is-primary" { "Save" }
+                @if let Some(page) = &page {
+                    a class="ml-3" href={"/status/"(page.slug)} target="_blank" { "/status/" (page.slug) }
+                    a class="ml-3" hx-get="/status-page/announcements" hx-target="#modal" hx-swap="outerHTML" {
+                        "Post announcements"
+                    }
+                }
+            }
+        }
+    };
+
+    let details = match &page {
+        Some(page) => {
+            let monitors = page_details(db, user_id, page.id).await;
+            html! {
+                (domain_box(page))
+                (monitors)
+            }
+        }
+        None => html! {},
+    };
+
+    (html! {
+        div class="modal is-active" id="modal" {
+            div class="modal-background" {}
+            div class="modal-content" style="width: 800px" {
+                div class="box" {
+                    h2 class="title" { "Status Page" }
+                    (page_form)
+                    (details)
+
+                    div style="width: 100%; display: flex; justify-content: flex-end;" class="mt-4" {
+                        button class="button is-danger is-light" hx-get=""
+                            hx-target="body" hx-swap="outherHTML"
+                            style="width:100px" {
+                            "Close"
+                        }
+                    }
+                }
+            }
+            button class="modal-close is-large" aria-label="close" {}
+        }
+    })
+    .into_string()
+}
+
+// the TXT value is derived from the page and domain so changing the domain needs a new record
+fn domain_token(page_id: Uuid, domain: &str) -> String {
+    crypto::sign(&ENV.secret, &format!("domain.{}.{}", page_id, domain))[..32].to_string()
+}
+
+fn app_host() -> String {
+    let host = ENV.host.split("://").last().unwrap_or(&ENV.host);
+    host.split(['/', ':']).next().unwrap_or(host).to_lowercase()
+}
+
+fn domain_box(page: &StatusPage) -> Markup {
+    html! {
+        h6 class="title is-size-5 mt-5" { "Custom Domain" }
+        form hx-post="/status-page/domain" hx-target="#modal" hx-swap="outerHTML" {
+            div class="field has-addons" {
+                div class="control flex1" {
+                    input class="input" name="custom_domain" placeholder="status.example.com"
+                        value=(page.custom_domain.clone().unwrap_or_default()) {}
+                }
+                div class="control" {
+                    button type="submit" class="button is-primary is-light" { "Save Domain" }
+                }
+            }
+        }
+        @if let Some(domain) = &page.custom_domain {
+            @if page.domain_verified_at.is_some() {
+                p class="mt-2" {
+                    span class="tag is-primary mr-2" { "verified" }
+                    a href={"https://"(domain)} target="_blank" { "https://" (domain) }
+                }
+            } @else {
+                div class="notification is-light mt-2 is-size-7" {
+                    p { "Add these DNS records, then verify the domain:" }
+                    table class="table is-size-7 mt-2 mb-2" style="width: 100%; background: none" {
+                        tr {
+                            td { "CNAME" }
+                            td { code { (domain) } }
+                            td { code { (app_host()) } }
+                        }
+                        tr {
+                            td { "TXT" }
+                            td { code { "_allgreen." (domain) } }
+                            td { code { "allgreen-verification=" (domain_token(page.id, domain)) } }
+                        }
+                    }
+                    p { "HTTPS for the domain has to be set up on the proxy in front of AllGreen." }
+                    button class="button is-small is-primary mt-2"
+                        hx-post="/status-page/domain/verify"
+                        hx-target="#modal" hx-swap="outerHTML" { "Verify" }
+                }
+            }
+        }
+    }
+}
+
+async fn page_details(db: &PgPool, user_id: Uuid, page_id: Uuid) -> Markup {
+    let sections = sqlx::query!(
+        r#"select id, name from status_page_section
+        where status_page_id = $1
+        order by position, name"#,
+        page_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let websites = sqlx::query!(
+        r#"select ws.id, ws.url, m.display_name as "display_name?", m.section_id as "section_id?",
+            m.website_id is not null as "included!"
+        from website ws
+        left join status_page_monitor m on m.website_id = ws.id and m.status_page_id = $2
+        where ws.user_id = $1
+        order by ws.url"#,
+        user_id,
+        page_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    html! {
+        h6 class="title is-size-5 mt-5" { "Sections" }
+        div class="field is-grouped is-grouped-multiline" {
+            @for section in sections.iter() {
+                div class="control" {
+                    div class="tags has-addons" {
+                        span class="tag is-medium" { (section.name) }
+                        a class="tag is-medium is-delete"
+                            hx-delete={"/status-page/sections/"(section.id.to_string())}
+                            hx-target="#modal" hx-swap="outerHTML" {}
+                    }
+                }
+            }
+        }
+        form hx-post="/status-page/sections" hx-target="#modal" hx-swap="outerHTML" {
+            div class="field has-addons" {
+                div class="control flex1" {
+                    input class="input" name="name" placeholder="Section name" required {}
+                }
+                div class="control" {
+                    button type="submit" class="button is-primary is-light" { "Add Section" }
+                }
+            }
+        }
+
+        h6 class="title is-size-5 mt-5" { "Monitors" }
+        table class="table is-size-7" style="width: 100%" {
+            tbody {
+                @for website in websites.iter() {
+                    tr {
+                        td colspan="3" {
+                            form class="is-flex is-align-items-center" style="gap: 8px"
+                                hx-post={"/status-page/monitors/"(website.id.to_string())}
+                                hx-trigger="change" hx-swap="none" {
+                                label class="checkbox flex1" style="word-break: break-all" {
+                                    input type="checkbox" name="included" value="true" checked[website.included] {}
+                                    " " (website.url)
+                                }
+                                input class="input is-small" name="display_name" placeholder="Display name"
+                                    style="width: 180px"
+                                    value=(website.display_name.clone().unwrap_or_default()) {}
+                                div class="select is-small" {
+                                    select name="section_id" {
+                                        option value="" { "No section" }
+                                        @for section in sections.iter() {
+                                            option value=(section.id.to_string())
+                                                selected[website.section_id == Some(section.id)] { (section.name) }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub async fn get_settings(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+) -> impl IntoResponse {
+    settings_modal(&db, user_id, None).await
+}
+
+#[derive(Deserialize)]
+pub struct PageParams {
+    pub title: String,
+    pub slug: String,
+    pub logo_url: String,
+    pub brand_color: String,
+    pub hide_urls: Option<String>,
+}
+
+fn valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+fn valid_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+pub async fn save(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Form(params): Form<PageParams>,
+) -> impl IntoResponse {
+    let slug = params.slug.trim().to_lowercase();
+    if !valid_slug(&slug) {
+        return settings_modal(
+            &db,
+            user_id,
+            Some("Use lowercase letters, digits and dashes"),
+        )
+        .await;
+    }
+    let brand_color = if valid_color(&params.brand_color) {
+        params.brand_color.clone()
+    } else {
+        "#00d1b2".to_string()
+    };
+    let logo_url = Some(params.logo_url.trim().to_string()).filter(|l| l.starts_with("https://"));
+
+    let saved = sqlx::query!(
+        r#"insert into status_page (user_id, slug, title, logo_url, brand_color, hide_urls)
+        values ($1, $2, $3, $4, $5, $6)
+        on conflict (user_id) do update set slug = excluded.slug, title = excluded.title,
+            logo_url = excluded.logo_url, brand_color = excluded.brand_color,
+            hide_urls = excluded.hide_urls"#,
+        user_id,
+        slug,
+        params.title.trim(),
+        logo_url,
+        brand_color,
+        params.hide_urls.is_some()
+    )
+    .execute(&db)
+    .await;
+
+    match saved {
+        Ok(_) => settings_modal(&db, user_id, None).await,
+        Err(_) => settings_modal(&db, user_id, Some("This address is already taken")).await,
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SectionParams {
+    pub name: String,
+}
+
+pub async fn add_section(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Form(params): Form<SectionParams>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"insert into status_page_section (status_page_id, name, position)
+        select p.id, $2, (select count(*)::int from status_page_section s where s.status_page_id = p.id)
+        from status_page p where p.user_id = $1"#,
+        user_id,
+        params.name.trim()
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    settings_modal(&db, user_id, None).await
+}
+
+pub async fn delete_section(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let section = sqlx::query!(
+        r#"select s.id from status_page_section s
+        join status_page p on p.id = s.status_page_id
+        where s.id = $1 and p.user_id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    if section.is_some() {
+        sqlx::query!(
+            r#"update status_page_monitor set section_id = null where section_id = $1"#,
+            id
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        sqlx::query!(r#"delete from status_page_section where id = $1"#, id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    settings_modal(&db, user_id, None).await
+}
+
+#[derive(Deserialize)]
+pub struct MonitorParams {
+    pub included: Option<String>,
+    pub display_name: String,
+    pub section_id: String,
+}
+
+pub async fn save_monitor(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(website_id): Path<Uuid>,
+    Form(params): Form<MonitorParams>,
+) -> impl IntoResponse {
+    if params.included.is_none() {
+        sqlx::query!(
+            r#"delete from status_page_monitor where website_id = $1
+            and status_page_id in (select id from status_page where user_id = $2)"#,
+            website_id,
+            user_id
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        return StatusCode::OK;
+    }
+
+    let display_name = Some(params.display_name.trim().to_string()).filter(|n| !n.is_empty());
+    let section_id = params.section_id.parse::<Uuid>().ok();
+
+    sqlx::query!(
+        r#"insert into status_page_monitor (status_page_id, website_id, section_id, display_name)
+        select p.id, ws.id, s.id, $4
+        from status_page p
+        join website ws on ws.user_id = p.user_id and ws.id = $2
+        left join status_page_section s on s.status_page_id = p.id and s.id = $3
+        where p.user_id = $1
+        on conflict (status_page_id, website_id) do update
+            set section_id = excluded.section_id, display_name = excluded.display_name"#,
+        user_id,
+        website_id,
+        section_id,
+        display_name
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    StatusCode::OK
+}
+
+#[derive(Deserialize)]
+pub struct DomainParams {
+    pub custom_domain: String,
+}
+
+fn valid_domain(domain: &str) -> bool {
+    domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        })
+}
+
+pub async fn save_domain(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Form(params): Form<DomainParams>,
+) -> impl IntoResponse {
+    let domain = params
+        .custom_domain
+        .trim()
+        .trim_end_matches('.')
+        .to_lowercase();
+    let domain = Some(domain).filter(|d| !d.is_empty());
+    if let Some(domain) = &domain {
+        if !valid_domain(domain) || *domain == app_host() {
+            return settings_modal(&db, user_id, Some("Invalid domain")).await;
+        }
+    }
+
+    // a saved domain is only a claim, it's unique once verified
+    sqlx::query!(
+        r#"update status_page set custom_domain = $2,
+            domain_verified_at = case when custom_domain = $2 then domain_verified_at end
+        where user_id = $1"#,
+        user_id,
+        domain
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    settings_modal(&db, user_id, None).await
+}
+
+fn txt_records(name: &str) -> Vec<String> {
+    let mut cmd = Command::new("dig");
+    cmd.arg("+short").arg("TXT").arg(name);
+    cmd.stdout(Stdio::piped());
+
+    let Ok(output) = cmd.execute_output() else {
+        println!("{} dig error", name);
+        return vec![];
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            line.replace("\" \"", "")
+                .replace('"', "")
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
+
+pub async fn verify_domain(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+) -> impl IntoResponse {
+    let page = sqlx::query!(
+        r#"select id, custom_domain from status_page where user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let Some((id, Some(domain))) = page.map(|p| (p.id, p.custom_domain)) else {
+        return settings_modal(&db, user_id, None).await;
+    };
+
+    let expected = format!("allgreen-verification={}", domain_token(id, &domain));
+    let name = format!("_allgreen.{}", domain);
+    let records = tokio::task::spawn_blocking(move || txt_records(&name))
+        .await
+        .unwrap_or_default();
+    if !records.contains(&expected) {
+        return settings_modal(
+            &db,
+            user_id,
+            Some("The TXT record was not found yet, DNS changes can take a while"),
+        )
+        .await;
+    }
+
+    // whoever controls the DNS now takes the domain over from other claims
+    sqlx::query!(
+        r#"update status_page set custom_domain = null, domain_verified_at = null
+        where custom_domain = $2 and id <> $1"#,
+        id,
+        domain
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        r#"update status_page set domain_verified_at = now() where id = $1 and custom_domain = $2"#,
+        id,
+        domain
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    settings_modal(&db, user_id, None).await
+}
+
+// requests on a verified custom domain get the status page at the root and only reach
+// the public routes of that page, the app and its session cookies stay on the app host
+pub async fn custom_domain(
+    State(db): State<PgPool>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .map(|h| h.split(':').next().unwrap_or(h).to_lowercase());
+
+    let Some(host) = host.filter(|h| *h != app_host()) else {
+        return next.run(request).await;
+    };
+
+    let page = sqlx::query_as!(
+        StatusPage,
+        r#"select id, slug, title, logo_url, brand_color, hide_urls,
+            custom_domain, domain_verified_at
+        from status_page where custom_domain = $1 and domain_verified_at is not null"#,
+        host
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let Some(page) = page else {
+        return next.run(request).await;
+    };
+
+    let path = request.uri().path();
+    let page_path = format!("/status/{}", page.slug);
+    if path == "/" {
+        render(&db, page).await
+    } else if path == page_path
+        || path.starts_with(&format!("{}/", page_path))
+        || path.starts_with("/badge/")
+    {
+        next.run(request).await
+    } else {
+        (StatusCode::NOT_FOUND, "Not found").into_response()
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_settings).post(save))
+        .route("/sections", post(add_section))
+        .route("/sections/:id", delete(delete_section))
+        .route("/monitors/:website_id", post(save_monitor))
+        .route("/domain", post(save_domain))
+        .route("/domain/verify", post(verify_domain))
+        .nest("/announcements", announcement::router())
+}
+
+pub fn public_router() -> Router<AppState> {
+    Router::new()
+        .route("/:slug", get(get_status_page))
+        .merge(announcement::public_router())
+}
@@ -1,5 +1,5 @@
 use crate::logged_user::LoggedUser;
-use crate::{AppState, checker};
+use crate::{appconfig::ENV, AppState, audit, checker, crypto, dependency, incident};
 use axum::http::HeaderMap;
 use axum::routing::{get, post};
 use axum::{extract::*, response::*, Router, http::StatusCode};
@@ -10,7 +10,7 @@ use sqlx::PgPool;
 use std::ops::Add;
 use uuid::Uuid;
 
-use super::layout;
+use super::{badge, layout};
 
 pub const DAY: i64 = 24 * 60 * 60;
 pub const WEEK: i64 = 24 * 60 * 60 * 7;
@@ -56,6 +56,99 @@ async fn get_website_create(
                 }
             }
 
+            div class="field" {
+                label class="label" {"IP Version"}
+                div class="control" {
+                    div class="select" {
+                        select name="ip_family" {
+                            option value="any" selected {"Any (resolver default)"}
+                            option value="ipv4" {"IPv4 only"}
+                            option value="ipv6" {"IPv6 only"}
+                            option value="both" {"IPv4 and IPv6 separately"}
+                        }
+                    }
+                }
+            }
+
+            div class="field" {
+                label class="label" {"Page Weight"}
+                div class="field is-grouped" {
+                    div class="control flex1" {
+                        input class="input" type="number" min="0" name="min_size" placeholder="Min bytes" value="" {}
+                    }
+                    div class="control flex1" {
+                        input class="input" type="number" min="0" name="max_size" placeholder="Max bytes" value="" {}
+                    }
+                    div class="control flex1" {
+                        input class="input" type="number" min="0" name="max_assets" placeholder="Max assets" value="" {}
+                    }
+                    div class="control flex1" {
+                        input class="input" type="number" min="0" name="max_assets_size" placeholder="Max asset bytes" value="" {}
+                    }
+                }
+                p class="help" {"Optional. Assets are the scripts, stylesheets and images referenced by the page."}
+            }
+
+            div class="field is-grouped is-align-items-center" {
+                div class="control" {
+                    label class="checkbox" {
+                        input type="checkbox" name="crawl_enabled" value="true" {}
+                        " Crawl daily for broken links, depth"
+                    }
+                }
+                div class="control" {
+                    input class="input is-small" type="number" name="crawl_depth" min="0" max="5"
+                        value="2" style="width: 70px" {}
+                }
+            }
+
+            div class="field" {
+                label class="label" {"Reminders"}
+                div class="field is-grouped" {
+                    div class="control flex1" {
+                        input class="input" type="number" min="1" name="reminder_interval" placeholder="Every minutes" value="" {}
+                    }
+                    div class="control flex1" {
+                        input class="input" type="number" min="1" name="reminder_limit" placeholder="At most times" value="" {}
+                    }
+                }
+                p class="help" {"Optional. Repeats the alert while the website stays down, until it is acknowledged."}
+            }
+
+            div class="field" {
+                label class="label" {"Authentication"}
+                div class="control" {
+                    div class="select" {
+                        select name="auth_type" {
+                            option value="none" selected {"None"}
+                            option value="basic" {"Basic"}
+                            option value="digest" {"Digest"}
+                        }
+                    }
+                }
+            }
+
+            div class="field is-grouped" {
+                div class="control has-icons-left flex1" {
+                    input class="input" placeholder="Username" name="auth_username" value=""  {}
+                    span class="icon is-tags is-left" {i class="fa-solid fa-user" {} }
+                }
+                div class="control has-icons-left flex1" {
+                    input class="input" type="password" placeholder="Password" name="auth_password"
+                        value="" autocomplete="new-password" {}
+                    span class="icon is-tags is-left" {i class="fa-solid fa-key" {} }
+                }
+            }
+
+            div class="field" {
+                label class="label" {"Headers"}
+                div class="control" {
+                    textarea class="textarea" rows="2" name="headers"
+                        placeholder="Authorization: Bearer {{secret:NAME}}" {}
+                }
+                p class="help" {"One header per line. Use {{secret:NAME}} to insert a stored secret."}
+            }
+
             div style="width: 100%; display: flex; justify-content: space-between;"{
                 div {
                     button type="submit" class="button is-primary" {
@@ -91,9 +184,369 @@ async fn get_website_create(
 }
 
 
+async fn channels_box(db: &PgPool, website_id: Uuid, user_id: Uuid) -> Markup {
+    let channels = sqlx::query!(
+        r#"select c.id, c.kind, c.name,
+            exists (select 1 from website_channel wc
+                where wc.website_id = $1 and wc.channel_id = c.id) as "selected!"
+        from notification_channel c
+        where c.user_id = $2
+        order by c.name"#,
+        website_id,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let policies = sqlx::query!(
+        r#"select p.id, p.name, ws.escalation_policy_id = p.id as "selected!"
+        from escalation_policy p
+        join website ws on ws.user_id = p.user_id
+        where ws.id = $1 and p.user_id = $2
+        order by p.name"#,
+        website_id,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    html! { div class="box" style="width: 100%" id="channels" {
+        h6 class="title is-size-5" { "Notifications" }
+        @if !policies.is_empty() {
+            div class="field" {
+                label class="label is-size-7" { "Escalation Policy" }
+                div class="select is-small" {
+                    select name="policy_id" hx-trigger="change"
+                        hx-post={"/website/"(website_id.to_string())"/escalation"}
+                        hx-target="#channels" hx-swap="outerHTML" {
+                        option value="" { "None" }
+                        @for policy in policies.iter() {
+                            option value=(policy.id.to_string()) selected[policy.selected] { (policy.name) }
+                        }
+                    }
+                }
+                @if policies.iter().any(|p| p.selected) {
+                    p class="help" { "State changes follow the escalation policy, other alerts use the channels below." }
+                }
+            }
+        }
+        @if channels.is_empty() {
+            p class="subtitle is-size-6" { "There is no notification channel, add one from settings." }
+        } @else {
+            @for channel in channels.iter() {
+                label class="checkbox mr-4" {
+                    input type="checkbox" checked[channel.selected]
+                        hx-post={"/website/"(website_id.to_string())"/channels/"(channel.id.to_string())}
+                        hx-target="#channels" hx-swap="outerHTML" {}
+                    " " (channel.name) " (" (channel.kind) ")"
+                }
+            }
+            @if !channels.iter().any(|c| c.selected) {
+                p class="help" { "No channel selected, all channels are notified." }
+            }
+        }
+    }}
+}
+
+pub async fn toggle_channel(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path((id, channel_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let deleted = sqlx::query!(
+        r#"delete from website_channel where website_id = $1 and channel_id = $2
+            and website_id in (select id from website where user_id = $3)"#,
+        id,
+        channel_id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    if deleted.rows_affected() == 0 {
+        sqlx::query!(
+            r#"insert into website_channel (website_id, channel_id)
+                select ws.id, c.id from website ws
+                join notification_channel c on c.user_id = ws.user_id
+                where ws.id = $1 and c.id = $2 and ws.user_id = $3"#,
+            id,
+            channel_id,
+            user_id
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+    }
+
+    channels_box(&db, id, user_id).await.into_string()
+}
+
+async fn dependencies_box(db: &PgPool, website_id: Uuid, user_id: Uuid) -> Markup {
+    let websites = sqlx::query!(
+        r#"select ws.id, ws.url,
+            exists (select 1 from website_dependency d
+                where d.website_id = $1 and d.parent_id = ws.id) as "is_parent!",
+            exists (select 1 from website_dependency d
+                where d.website_id = ws.id and d.parent_id = $1) as "is_child!"
+        from website ws
+        where ws.user_id = $2 and ws.id <> $1
+        order by ws.url"#,
+        website_id,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    let parents = dependency::parents(db).await;
+
+    html! { div class="box" style="width: 100%" id="dependencies" {
+        h6 class="title is-size-5" { "Dependencies" }
+        p class="help mb-2" { "While a website this one depends on is down, its failures are marked dependency_down and don't alert." }
+        table class="table" style="width: 100%" {
+            tbody {
+                @for parent in websites.iter().filter(|w| w.is_parent) {
+                    tr {
+                        td { "Depends on" }
+                        td style="word-break: break-all" { a href={"/website/"(parent.id.to_string())} { (parent.url) } }
+                        td {
+                            button class="button is-danger is-inverted is-small"
+                                hx-delete={"/website/"(website_id.to_string())"/dependencies/"(parent.id.to_string())}
+                                hx-target="#dependencies" hx-swap="outerHTML" {
+                                i class="fa fa-trash" {}
+                            }
+                        }
+                    }
+                }
+                @for child in websites.iter().filter(|w| w.is_child) {
+                    tr {
+                        td { "Required by" }
+                        td style="word-break: break-all" { a href={"/website/"(child.id.to_string())} { (child.url) } }
+                        td {}
+                    }
+                }
+            }
+        }
+        form hx-post={"/website/"(website_id.to_string())"/dependencies"}
+            hx-target="#dependencies" hx-swap="outerHTML" {
+            div class="field has-addons" {
+                div class="control flex1" {
+                    div class="select is-small is-fullwidth" {
+                        select name="parent_id" {
+                            @for candidate in websites.iter()
+                                .filter(|w| !w.is_parent && !dependency::would_cycle(website_id, w.id, &parents)) {
+                                option value=(candidate.id.to_string()) { (candidate.url) }
+                            }
+                        }
+                    }
+                }
+                div class="control" {
+                    button type="submit" class="button is-small is-primary" { "Add" }
+                }
+            }
+        }
+    }}
+}
+
+#[derive(Deserialize)]
+pub struct DependencyParams {
+    pub parent_id: Uuid,
+}
+
+pub async fn add_dependency(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+    Form(params): Form<DependencyParams>,
+) -> impl IntoResponse {
+    let parents = dependency::parents(&db).await;
+
+    if !dependency::would_cycle(id, params.parent_id, &parents) {
+        sqlx::query!(
+            r#"insert into website_dependency (website_id, parent_id)
+                select ws.id, parent.id from website ws
+                join website parent on parent.user_id = ws.user_id
+                where ws.id = $1 and parent.id = $2 and ws.user_id = $3
+                on conflict do nothing"#,
+            id,
+            params.parent_id,
+            user_id
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+    }
+
+    dependencies_box(&db, id, user_id).await.into_string()
+}
+
+pub async fn delete_dependency(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path((id, parent_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"delete from website_dependency where website_id = $1 and parent_id = $2
+            and website_id in (select id from website where user_id = $3)"#,
+        id,
+        parent_id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    dependencies_box(&db, id, user_id).await.into_string()
+}
+
+async fn badges_box(db: &PgPool, website_id: Uuid, user_id: Uuid) -> Markup {
+    let website = sqlx::query!(
+        r#"select badge_token from website where id = $1 and user_id = $2"#,
+        website_id,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap();
+
+    let Some(website) = website else {
+        return html! {};
+    };
+
+    html! { div class="box" style="width: 100%" id="badges" {
+        h6 class="title is-size-5" { "Badges" }
+        @if let Some(token) = &website.badge_token {
+            p class="help mb-2" { "Anyone with these links can see the badges, reset them to revoke access." }
+            @for (metric, _) in badge::METRICS.iter() {
+                @let url = format!("{}/badge/{}.svg?metric={}", ENV.host, token, metric);
+                div class="field" {
+                    img src=(url) {}
+                    input class="input is-small" readonly value={"![" (metric) "](" (url) ")"} {}
+                }
+            }
+        }
+        button class="button is-small is-primary is-light"
+            hx-post={"/website/"(website_id.to_string())"/badge"}
+            hx-confirm=[website.badge_token.as_ref().map(|_| "The current badge links will stop working, continue?")]
+            hx-target="#badges" hx-swap="outerHTML" {
+            @if website.badge_token.is_some() { "Reset badge links" } @else { "Create badge links" }
+        }
+    }}
+}
+
+pub async fn reset_badge(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    sqlx::query!(
+        r#"update website set badge_token = $3 where id = $1 and user_id = $2"#,
+        id,
+        user_id,
+        Uuid::new_v4().simple().to_string()
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    badges_box(&db, id, user_id).await.into_string()
+}
+
+#[derive(Deserialize)]
+pub struct EscalationParams {
+    pub policy_id: String,
+}
+
+pub async fn set_escalation(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+    Form(params): Form<EscalationParams>,
+) -> impl IntoResponse {
+    let policy_id = params.policy_id.parse::<Uuid>().ok();
+
+    sqlx::query!(
+        r#"update website set escalation_policy_id = $1
+            where id = $2 and user_id = $3 and ($1::uuid is null or exists (
+                select 1 from escalation_policy where id = $1 and user_id = $3))"#,
+        policy_id,
+        id,
+        user_id
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    channels_box(&db, id, user_id).await.into_string()
+}
+
+async fn incident_box(db: &PgPool, website_id: Uuid, user_id: Uuid) -> Markup {
+    let incident = sqlx::query!(
+        r#"select i.id, i.opened_at, i.acknowledged_at, i.acknowledged_by from incident i
+        join website ws on ws.id = i.website_id
+        where i.website_id = $1 and ws.user_id = $2 and i.resolved_at is null"#,
+        website_id,
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap();
+
+    html! { div id="incident" style="width: 100%" {
+        @if let Some(incident) = incident {
+            div class="notification is-danger is-light is-flex is-justify-content-space-between is-align-items-center" {
+                @if let Some(acknowledged_at) = incident.acknowledged_at {
+                    span {
+                        a href={"/incident/"(incident.id.to_string())} { "Incident" }
+                        " open since " (incident.opened_at.format("%H:%M %d/%m/%Y").to_string())
+                        ", acknowledged by " (incident.acknowledged_by.unwrap_or_default())
+                        " at " (acknowledged_at.format("%H:%M %d/%m/%Y").to_string())
+                    }
+                } @else {
+                    span {
+                        a href={"/incident/"(incident.id.to_string())} { "Incident" }
+                        " open since " (incident.opened_at.format("%H:%M %d/%m/%Y").to_string())
+                    }
+                    button class="button is-danger is-small"
+                        hx-post={"/website/"(website_id.to_string())"/ack"}
+                        hx-target="#incident" hx-swap="outerHTML" {
+                        "Acknowledge"
+                    }
+                }
+            }
+        }
+    }}
+}
+
+pub async fn acknowledge(
+    State(db): State<PgPool>,
+    LoggedUser(user_id): LoggedUser,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let user = sqlx::query!(
+        r#"select u.email from "user" u
+        join website ws on ws.user_id = u.id
+        where ws.id = $1 and u.id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    if let Some(user) = user {
+        incident::acknowledge(&db, id, &user.email).await;
+    }
+
+    incident_box(&db, id, user_id).await.into_string()
+}
+
 pub async fn get_website(
     State(db): State<PgPool>,
-    LoggedUser(_): LoggedUser,
+    LoggedUser(user_id): LoggedUser,
     Path(id): Path<Uuid>,
 ) -> impl IntoResponse {
     let ws = sqlx::query!(r#"
@@ -106,10 +559,13 @@ pub async fn get_website(
             from website_state
             order by rn 
         ) as last on last.website_id = ws.id and last.rn = 1
-        where ws.id = $1 "#, id)
-        .fetch_one(&db)
+        where ws.id = $1 and ws.user_id = $2 "#, id, user_id)
+        .fetch_optional(&db)
         .await
         .unwrap();
+    let Some(ws) = ws else {
+        return (StatusCode::NOT_FOUND, "Website not found").into_response();
+    };
     let now = Utc::now();
     let yesterday = now.add(Duration::days(-1));
     let lastweek = now.add(Duration::days(-7));
@@ -132,6 +588,10 @@ pub async fn get_website(
     let mut last24_uptime = 0;
     let mut lastweek_uptime = 0;
     let mut lastyear_uptime = 0;
+    // maintenance neither counts as up nor as down
+    let mut last24_maintenance = 0;
+    let mut lastweek_maintenance = 0;
+    let mut lastyear_maintenance = 0;
 
     let mut dt = now;
     for s in history.iter() {
@@ -139,6 +599,10 @@ pub async fn get_website(
         let state_color = 
             if s.state == "up" { 
                 "has-background-primary" 
+            } else if s.state == "degraded" {
+                "has-background-warning"
+            } else if s.state == "maintenance" {
+                "has-background-info"
             } else { 
                 "has-background-danger" 
             };
@@ -154,6 +618,8 @@ pub async fn get_website(
 
             if s.state == "up" {
                 last24_uptime += len;
+            } else if s.state == "maintenance" {
+                last24_maintenance += len;
             }
         }
         if s.created_at >= lastweek {
@@ -168,6 +634,8 @@ pub async fn get_website(
 
             if s.state == "up" {
                 lastweek_uptime += len;
+            } else if s.state == "maintenance" {
+                lastweek_maintenance += len;
             }
         }
         if s.created_at >= lastyear {
@@ -182,6 +650,8 @@ pub async fn get_website(
 
             if s.state == "up" {
                 lastyear_uptime += len;
+            } else if s.state == "maintenance" {
+                lastyear_maintenance += len;
             }
         }
 
@@ -197,6 +667,8 @@ pub async fn get_website(
 
             if s.state == "up" {
                 last24_uptime += len;
+            } else if s.state == "maintenance" {
+                last24_maintenance += len;
             }
         }
         if s.created_at < lastweek && dt > lastweek {
@@ -211,6 +683,8 @@ pub async fn get_website(
 
             if s.state == "up" {
                 lastweek_uptime += len;
+            } else if s.state == "maintenance" {
+                lastweek_maintenance += len;
             }
         }
 
@@ -226,6 +700,8 @@ pub async fn get_website(
 
             if s.state == "up" {
                 lastyear_uptime += len;
+            } else if s.state == "maintenance" {
+                lastyear_maintenance += len;
             }
         }
 
@@ -246,14 +722,20 @@ pub async fn get_website(
         lastyear_uptime += (last.created_at - lastyear).num_seconds();
     }
 
-    let last24_uptime = format!("{:.2}%", last24_uptime as f64 / DAY as f64 * 100f64);
-    let lastweek_uptime = format!("{:.2}%", lastweek_uptime as f64 / WEEK as f64 * 100f64);
-    let lastyear_uptime = format!("{:.2}%", lastyear_uptime as f64 / YEAR as f64 * 100f64);
+    let uptime = |up: i64, maintenance: i64, period: i64| {
+        format!("{:.2}%", up as f64 / (period - maintenance).max(1) as f64 * 100f64)
+    };
+    let last24_uptime = uptime(last24_uptime, last24_maintenance, DAY);
+    let lastweek_uptime = uptime(lastweek_uptime, lastweek_maintenance, WEEK);
+    let lastyear_uptime = uptime(lastyear_uptime, lastyear_maintenance, YEAR);
 
     let last_state_color = history.first()
         .map(|x| match x.state.as_str() { 
             "up" => "has-text-primary",
             "down" => "has-text-danger",
+            "degraded" => "has-text-warning",
+            "maintenance" => "has-text-info",
+            "dependency_down" => "has-text-danger",
             _ => "has-text-dark"
         })
         .unwrap_or("has-text-dark");
@@ -299,6 +781,108 @@ pub async fn get_website(
                     }
                 }
 
+                div class="field" {
+                    label class="label" {"IP Version"}
+                    div class="control" {
+                        div class="select" {
+                            select name="ip_family" {
+                                option value="any" selected[ws.ip_family == "any"] {"Any (resolver default)"}
+                                option value="ipv4" selected[ws.ip_family == "ipv4"] {"IPv4 only"}
+                                option value="ipv6" selected[ws.ip_family == "ipv6"] {"IPv6 only"}
+                                option value="both" selected[ws.ip_family == "both"] {"IPv4 and IPv6 separately"}
+                            }
+                        }
+                    }
+                }
+
+                div class="field" {
+                    label class="label" {"Page Weight"}
+                    div class="field is-grouped" {
+                        div class="control flex1" {
+                            input class="input" type="number" min="0" name="min_size" placeholder="Min bytes"
+                                value=(ws.min_size.map(|v| v.to_string()).unwrap_or_default()) {}
+                        }
+                        div class="control flex1" {
+                            input class="input" type="number" min="0" name="max_size" placeholder="Max bytes"
+                                value=(ws.max_size.map(|v| v.to_string()).unwrap_or_default()) {}
+                        }
+                        div class="control flex1" {
+                            input class="input" type="number" min="0" name="max_assets" placeholder="Max assets"
+                                value=(ws.max_assets.map(|v| v.to_string()).unwrap_or_default()) {}
+                        }
+                        div class="control flex1" {
+                            input class="input" type="number" min="0" name="max_assets_size" placeholder="Max asset bytes"
+                                value=(ws.max_assets_size.map(|v| v.to_string()).unwrap_or_default()) {}
+                        }
+                    }
+                    p class="help" {"Optional. Assets are the scripts, stylesheets and images referenced by the page."}
+                }
+
+                div class="field is-grouped is-align-items-center" {
+                    div class="control" {
+                        label class="checkbox" {
+                            input type="checkbox" name="crawl_enabled" value="true" checked[ws.crawl_enabled] {}
+                            " Crawl daily for broken links, depth"
+                        }
+                    }
+                    div class="control" {
+                        input class="input is-small" type="number" name="crawl_depth" min="0" max="5"
+                            value=(ws.crawl_depth) style="width: 70px" {}
+                    }
+                }
+
+                div class="field" {
+                    label class="label" {"Reminders"}
+                    div class="field is-grouped" {
+                        div class="control flex1" {
+                            input class="input" type="number" min="1" name="reminder_interval" placeholder="Every minutes"
+                                value=(ws.reminder_interval.map(|v| v.to_string()).unwrap_or_default()) {}
+                        }
+                        div class="control flex1" {
+                            input class="input" type="number" min="1" name="reminder_limit" placeholder="At most times"
+                                value=(ws.reminder_limit.map(|v| v.to_string()).unwrap_or_default()) {}
+                        }
+                    }
+                    p class="help" {"Optional. Repeats the alert while the website stays down, until it is acknowledged."}
+                }
+
+                div class="field" {
+                    label class="label" {"Authentication"}
+                    div class="control" {
+                        div class="select" {
+                            select name="auth_type" {
+                                option value="none" selected[ws.auth_type.is_none()] {"None"}
+                                option value="basic" selected[ws.auth_type.as_deref() == Some("basic")] {"Basic"}
+                                option value="digest" selected[ws.auth_type.as_deref() == Some("digest")] {"Digest"}
+                            }
+                        }
+                    }
+                }
+
+                div class="field is-grouped" {
+                    div class="control has-icons-left flex1" {
+                        input class="input" placeholder="Username" name="auth_username"
+                            value=(ws.auth_username.clone().unwrap_or("".to_string()))  {}
+                        span class="icon is-tags is-left" {i class="fa-solid fa-user" {} }
+                    }
+                    div class="control has-icons-left flex1" {
+                        // the stored password is never sent back, an empty value keeps it
+                        input class="input" type="password" name="auth_password" value=""
+                            autocomplete="new-password"
+                            placeholder=(if ws.auth_password.is_some() { "Unchanged" } else { "Password" }) {}
+                        span class="icon is-tags is-left" {i class="fa-solid fa-key" {} }
+                    }
+                }
+
+                div class="field" {
+                    label class="label" {"Headers"}
+                    div class="control" {
+                        textarea class="textarea" rows="2" name="headers"
+                            placeholder="Authorization: Bearer {{secret:NAME}}" { (ws.headers) }
+                    }
+                    p class="help" {"One header per line. Use {{secret:NAME}} to insert a stored secret."}
+                }
+
                 div style="width: 100%; display: flex; justify-content: space-between;"{
                     div {
                         button type="submit" class="button is-primary" {
@@ -363,6 +947,7 @@ pub async fn get_website(
             thead {
                 tr {
                     th {"State"}
+                    th {"Reason"}
                     th {"Checked"}
                 }
             }
@@ -374,12 +959,18 @@ pub async fn get_website(
                                 match h.state.as_str() { 
                                     "up" => "has-text-primary",
                                     "down" =>  "has-text-danger",
+                                    "degraded" => "has-text-warning",
+                                    "maintenance" => "has-text-info",
+                                    "dependency_down" => "has-text-danger",
                                     _ => "has-text-darl"  
                                 })
                             } {
                                 (h.state)
                             }
                         }
+                        td {
+                            (h.reason.clone().unwrap_or("".to_string()))
+                        }
                         td {
                             (h.created_at.format("%H:%M %d/%m/%Y").to_string())
                         }
@@ -389,6 +980,129 @@ pub async fn get_website(
         }
     }};
 
+    let audit = sqlx::query!(
+        r#"select * from security_audit
+            where website_id = $1
+            order by created_at desc limit 1"#,
+        id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let audit_panel = html! { div class="box" style="width: 100%" {
+        h6 class="title is-size-5" { "Security Audit" }
+        @if let Some(audit) = &audit {
+            table class="table" style="width: 100%" {
+                tbody {
+                    @for (name, value) in [
+                        ("Strict-Transport-Security", &audit.hsts),
+                        ("Content-Security-Policy", &audit.csp),
+                        ("X-Frame-Options", &audit.x_frame_options),
+                        ("X-Content-Type-Options", &audit.x_content_type_options),
+                    ] {
+                        tr {
+                            td { (name) }
+                            @if let Some(value) = value {
+                                td class="has-text-primary" style="word-break: break-all" { (value) }
+                            } @else {
+                                td class="has-text-danger" { "missing" }
+                            }
+                        }
+                    }
+                    tr {
+                        td { "Cookies" }
+                        td {
+                            @for cookie in audit.cookie_flags.lines() {
+                                div { (cookie) }
+                            }
+                        }
+                    }
+                    tr {
+                        td { "TLS Versions" }
+                        td {
+                            @for version in audit.tls_versions.split(',').filter(|v| !v.is_empty()) {
+                                span class={"tag mr-1 " (if audit::OLD_TLS.contains(&version) { "is-danger" } else { "is-primary" })} {
+                                    (version)
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            @for issue in audit.issues.lines() {
+                p class="has-text-danger" { (issue) }
+            }
+            p class="is-size-7" { "Audited at " (audit.created_at.format("%H:%M %d/%m/%Y").to_string()) }
+        } @else {
+            p class="subtitle is-size-6" { "Not audited yet" }
+        }
+    }};
+
+    let crawl = sqlx::query!(
+        r#"select * from crawl_run
+            where website_id = $1 and finished_at is not null
+            order by created_at desc limit 1"#,
+        id
+    )
+    .fetch_optional(&db)
+    .await
+    .unwrap();
+
+    let crawl_issues = match &crawl {
+        Some(crawl) => sqlx::query!(
+            r#"select * from crawl_issue where crawl_run_id = $1 order by url"#,
+            crawl.id
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap(),
+        None => vec![],
+    };
+
+    let crawl_panel = html! { div class="box" style="width: 100%" {
+        h6 class="title is-size-5" { "Broken Links" }
+        @if let Some(crawl) = &crawl {
+            p class="is-size-7 mb-2" {
+                (crawl.pages) " pages checked at " (crawl.created_at.format("%H:%M %d/%m/%Y").to_string())
+            }
+            @if crawl_issues.is_empty() {
+                p class="has-text-primary" { "No broken links found" }
+            } @else {
+                table class="table" style="width: 100%" {
+                    thead {
+                        tr {
+                            th {"Page"}
+                            th {"Problem"}
+                        }
+                    }
+                    tbody {
+                        @for issue in crawl_issues.iter() {
+                            tr {
+                                td style="word-break: break-all" {
+                                    a href=(issue.url) target="_blank" { (issue.url) }
+                                }
+                                td class="has-text-danger" {
+                                    @if let Some(status) = issue.status { (status) " " }
+                                    (issue.error.clone().unwrap_or("".to_string()))
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        } @else if ws.crawl_enabled {
+            p class="subtitle is-size-6" { "Not crawled yet" }
+        } @else {
+            p class="subtitle is-size-6" { "Crawling is disabled for this website" }
+        }
+    }};
+
+    let channels_panel = channels_box(&db, id, user_id).await;
+    let incident_panel = incident_box(&db, id, user_id).await;
+    let dependencies_panel = dependencies_box(&db, id, user_id).await;
+    let badges_panel = badges_box(&db, id, user_id).await;
+
     layout::page(html!{}, html! { 
         div class="has-background-white p-5 is-flex is-flex-direction-row" style="min-height: 100vh"
             hx-trigger="every 10s" hx-get="" hx-swap="multi:#websites:outerHTML,#summary:outerHTML"   {
@@ -422,6 +1136,10 @@ pub async fn get_website(
                              (ws.url)            
                        }
                        " is " (last_state_text)
+                       @if ws.is_flapping {
+                           span class="tag is-warning ml-2" aria-label="Alerts are paused until it is stable"
+                               data-cooltipz-dir="bottom" { "flapping" }
+                       }
                     }
 
                     h5 class="title m-0 is-size-6" {
@@ -440,8 +1158,14 @@ pub async fn get_website(
                                 h3 class="subtitle" { "No data yet" }
                             }
                         } @else {
+                            ( incident_panel )
                             ( bars )
                             ( history_table )
+                            ( channels_panel )
+                            ( dependencies_panel )
+                            ( badges_panel )
+                            ( audit_panel )
+                            ( crawl_panel )
                         }
                     }
                 }
@@ -458,6 +1182,69 @@ pub struct WebSiteUpdateParams {
     pub keyword: String,
     pub tags: String,
     pub useragent: String,
+    pub auth_type: String,
+    pub auth_username: String,
+    pub auth_password: String,
+    pub headers: String,
+    pub ip_family: String,
+    pub crawl_enabled: Option<String>,
+    pub crawl_depth: String,
+    pub min_size: String,
+    pub max_size: String,
+    pub max_assets: String,
+    pub max_assets_size: String,
+    pub reminder_interval: String,
+    pub reminder_limit: String,
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Option<T> {
+    value.trim().parse::<T>().ok()
+}
+
+// page weight limits below zero are dropped instead of saved
+fn limit<T: std::str::FromStr + PartialOrd + Default>(value: &str) -> Option<T> {
+    number::<T>(value).filter(|v| *v >= T::default())
+}
+
+impl WebSiteUpdateParams {
+    fn auth_type(&self) -> Option<String> {
+        match self.auth_type.as_str() {
+            "basic" | "digest" => Some(self.auth_type.clone()),
+            _ => None,
+        }
+    }
+
+    fn ip_family(&self) -> String {
+        match self.ip_family.as_str() {
+            "ipv4" | "ipv6" | "both" => self.ip_family.clone(),
+            _ => "any".to_string(),
+        }
+    }
+
+    fn crawl_enabled(&self) -> bool {
+        self.crawl_enabled.is_some()
+    }
+
+    // an empty input keeps the default depth of the column
+    fn crawl_depth(&self) -> i32 {
+        number::<i32>(&self.crawl_depth).unwrap_or(2).clamp(0, 5)
+    }
+
+    fn auth_username(&self) -> Option<String> {
+        if self.auth_type().is_none() || self.auth_username.is_empty() {
+            None
+        } else {
+            Some(self.auth_username.clone())
+        }
+    }
+
+    fn auth_password(&self) -> Option<String> {
+        if self.auth_type().is_none() || self.auth_password.is_empty() {
+            None
+        } else {
+            Some(crypto::encrypt(&self.auth_password))
+        }
+    }
 }
 
 pub async fn update(
@@ -466,20 +1253,32 @@ pub async fn update(
     Path(id): Path<Uuid>,
     Form(params): Form<WebSiteUpdateParams>,
 ) -> impl IntoResponse {
-    let useragent = if params.useragent.len() == 0 {
-        None
-    } else {
-        Some(params.useragent)
-    };
-
-    let update = sqlx::query_as!(
-        Website,
-        r#"update website set url = $1, keyword=$2, tags = $3 where id = $4 and user_id = $5"#,
+    let update = sqlx::query!(
+        r#"update website set url = $1, keyword=$2, tags = $3,
+            auth_type = $6, auth_username = $7,
+            auth_password = case when $6::varchar is null then null else coalesce($8, auth_password) end,
+            headers = $9, ip_family = $10, crawl_enabled = $11, crawl_depth = $12,
+            min_size = $13, max_size = $14, max_assets = $15, max_assets_size = $16,
+            reminder_interval = $17, reminder_limit = $18
+        where id = $4 and user_id = $5"#,
         params.url,
         params.keyword,
         params.tags,
         id,
-        user_id
+        user_id,
+        params.auth_type(),
+        params.auth_username(),
+        params.auth_password(),
+        params.headers,
+        params.ip_family(),
+        params.crawl_enabled(),
+        params.crawl_depth(),
+        limit::<i64>(&params.min_size),
+        limit::<i64>(&params.max_size),
+        limit::<i32>(&params.max_assets),
+        limit::<i64>(&params.max_assets_size),
+        number::<i32>(&params.reminder_interval),
+        number::<i32>(&params.reminder_limit)
     )
     .execute(&db)
     .await;
@@ -512,12 +1311,20 @@ pub async fn create(
     let useragent = if params.useragent.len() == 0 {
         None
     } else {
-        Some(params.useragent)
+        Some(params.useragent.clone())
     };
     let website = sqlx::query!(r#"
-        insert into website(user_id, keyword, url, tags , useragent) 
-        values ($1, $2, $3, $4, $5) returning *"#, 
-            user_id, params.keyword, params.url, params.tags, useragent )
+        insert into website(user_id, keyword, url, tags , useragent,
+            auth_type, auth_username, auth_password, headers, ip_family, crawl_enabled, crawl_depth,
+            min_size, max_size, max_assets, max_assets_size, reminder_interval, reminder_limit) 
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+        returning *"#, 
+            user_id, params.keyword, params.url, params.tags, useragent,
+            params.auth_type(), params.auth_username(), params.auth_password(), params.headers,
+            params.ip_family(), params.crawl_enabled(), params.crawl_depth(),
+            limit::<i64>(&params.min_size), limit::<i64>(&params.max_size),
+            limit::<i32>(&params.max_assets), limit::<i64>(&params.max_assets_size),
+            number::<i32>(&params.reminder_interval), number::<i32>(&params.reminder_limit) )
         .fetch_one(&db).await
         .unwrap();
 
@@ -555,6 +1362,86 @@ pub async fn delete(
             .await
             .unwrap();
 
+        sqlx::query!(r#"delete from website_channel where website_id = $1"#, id)
+            .execute(&client)
+            .await
+            .unwrap();
+
+        sqlx::query!(r#"delete from notification_delivery where website_id = $1"#, id)
+            .execute(&client)
+            .await
+            .unwrap();
+
+        sqlx::query!(
+            r#"delete from incident_reminder
+                where incident_id in (select id from incident where website_id = $1)"#,
+            id
+        )
+        .execute(&client)
+        .await
+        .unwrap();
+
+        sqlx::query!(
+            r#"delete from incident_event
+                where incident_id in (select id from incident where website_id = $1)"#,
+            id
+        )
+        .execute(&client)
+        .await
+        .unwrap();
+
+        sqlx::query!(r#"delete from incident where website_id = $1"#, id)
+            .execute(&client)
+            .await
+            .unwrap();
+
+        sqlx::query!(
+            r#"delete from website_dependency where website_id = $1 or parent_id = $1"#,
+            id
+        )
+        .execute(&client)
+        .await
+        .unwrap();
+
+        sqlx::query!(r#"delete from website_response where website_id = $1"#, id)
+            .execute(&client)
+            .await
+            .unwrap();
+
+        sqlx::query!(r#"delete from status_page_monitor where website_id = $1"#, id)
+            .execute(&client)
+            .await
+            .unwrap();
+
+        sqlx::query!(r#"delete from maintenance_window where website_id = $1"#, id)
+            .execute(&client)
+            .await
+            .unwrap();
+
+        sqlx::query!(r#"delete from expiry_alert where website_id = $1"#, id)
+            .execute(&client)
+            .await
+            .unwrap();
+
+        sqlx::query!(r#"delete from security_audit where website_id = $1"#, id)
+            .execute(&client)
+            .await
+            .unwrap();
+
+        sqlx::query!(
+            r#"delete from crawl_issue
+                where crawl_run_id in (select id from crawl_run where website_id = $1)"#,
+            id
+        )
+        .execute(&client)
+        .await
+        .unwrap();
+
+        sqlx::query!(r#"delete from crawl_run where website_id = $1"#, id)
+            .execute(&client)
+            .await
+            .unwrap();
+
         sqlx::query!(
             r#"delete from website where user_id = $1 and id = $2"#,
             user_id,
@@ -622,4 +1509,13 @@ pub fn router() -> Router<AppState> {
         )
         .route("/:id/pause", post(pause))
         .route("/:id/play", post(play))
+        .route("/:id/channels/:channel_id", post(toggle_channel))
+        .route("/:id/escalation", post(set_escalation))
+        .route("/:id/ack", post(acknowledge))
+        .route("/:id/dependencies", post(add_dependency))
+        .route("/:id/badge", post(reset_badge))
+        .route(
+            "/:id/dependencies/:parent_id",
+            axum::routing::delete(delete_dependency),
+        )
 }
@@ -22,7 +22,10 @@ pub struct AppConfig {
     pub checker_sleep: u64,
     pub checker_log: bool,
     pub mailer: String,
-    pub mailer_password: String
+    pub mailer_password: String,
+    pub telegram_api_url: Option<String>,
+    pub pagerduty_api_url: Option<String>,
+    pub opsgenie_api_url: Option<String>,
 }
 
 pub static ENV: Lazy<AppConfig> = Lazy::new(|| {
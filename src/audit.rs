@@ -0,0 +1,240 @@
+use crate::checker::{self, CheckOptions, HTTP};
+use crate::notification::{self, Event, EventKind};
+use chrono::{Days, Utc};
+use execute::Execute;
+use reqwest::header::{
+    HeaderMap, CONTENT_SECURITY_POLICY, SET_COOKIE, STRICT_TRANSPORT_SECURITY,
+    X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use reqwest::Method;
+use sqlx::{Pool, Postgres};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+pub const OLD_TLS: [&str; 4] = ["SSLv2", "SSLv3", "TLSv1.0", "TLSv1.1"];
+
+#[derive(Default)]
+pub struct Audit {
+    pub hsts: Option<String>,
+    pub csp: Option<String>,
+    pub x_frame_options: Option<String>,
+    pub x_content_type_options: Option<String>,
+    pub cookie_flags: String,
+    pub tls_versions: String,
+}
+
+fn header(headers: &HeaderMap, name: reqwest::header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|h| h.to_str().ok())
+        .map(|h| h.to_string())
+}
+
+// one line per cookie: "name: Secure, HttpOnly, SameSite=Lax"
+fn cookie_flags(headers: &HeaderMap) -> String {
+    headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .map(|cookie| {
+            let mut parts = cookie.split(';').map(|p| p.trim());
+            let name = parts
+                .next()
+                .and_then(|p| p.split('=').next())
+                .unwrap_or("")
+                .to_string();
+            let flags: Vec<&str> = parts
+                .filter(|p| {
+                    let p = p.to_lowercase();
+                    p == "secure" || p == "httponly" || p.starts_with("samesite")
+                })
+                .collect();
+            format!("{}: {}", name, flags.join(", "))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn tls_versions(host: &str) -> Option<String> {
+    let mut cmd = Command::new("nmap");
+    cmd.arg("-p")
+        .arg("443")
+        .arg("--script")
+        .arg("ssl-enum-ciphers")
+        .arg(host);
+    cmd.stdout(Stdio::piped());
+
+    let output = cmd.execute_output().ok()?;
+    let output = String::from_utf8(output.stdout).ok()?;
+
+    let versions: Vec<String> = output
+        .split("\n")
+        .map(|l| l.trim_start_matches('|').trim())
+        .filter(|l| l.ends_with(':') && (l.starts_with("TLSv") || l.starts_with("SSLv")))
+        .map(|l| l.trim_end_matches(':').to_string())
+        .collect();
+
+    Some(versions.join(","))
+}
+
+// the flag is false when the TLS versions couldn't be looked up
+pub async fn audit(opts: &CheckOptions) -> Option<(Audit, bool)> {
+    let resp = checker::send(&HTTP, opts, Method::GET, &opts.url, &checker::useragent(opts))
+        .await
+        .ok()?;
+    let headers = resp.headers();
+
+    let host = resp.url().host_str().unwrap_or("").to_string();
+    let tls_versions = if resp.url().scheme() == "https" {
+        // nmap blocks for the whole scan, keep it off the runtime workers
+        tokio::task::spawn_blocking(move || tls_versions(&host))
+            .await
+            .unwrap_or(None)
+    } else {
+        Some("".to_string())
+    };
+    let tls_checked = tls_versions.is_some();
+
+    Some((
+        Audit {
+            hsts: header(headers, STRICT_TRANSPORT_SECURITY),
+            csp: header(headers, CONTENT_SECURITY_POLICY),
+            x_frame_options: header(headers, X_FRAME_OPTIONS),
+            x_content_type_options: header(headers, X_CONTENT_TYPE_OPTIONS),
+            cookie_flags: cookie_flags(headers),
+            tls_versions: tls_versions.unwrap_or_default(),
+        },
+        tls_checked,
+    ))
+}
+
+// compares with the previous audit, only changes for the worse are reported
+pub fn issues(previous: &Audit, current: &Audit) -> Vec<String> {
+    let mut issues = vec![];
+
+    let headers = [
+        ("Strict-Transport-Security", &previous.hsts, &current.hsts),
+        ("Content-Security-Policy", &previous.csp, &current.csp),
+        ("X-Frame-Options", &previous.x_frame_options, &current.x_frame_options),
+        (
+            "X-Content-Type-Options",
+            &previous.x_content_type_options,
+            &current.x_content_type_options,
+        ),
+    ];
+    for (name, previous, current) in headers {
+        if previous.is_some() && current.is_none() {
+            issues.push(format!("{} header disappeared", name));
+        }
+    }
+
+    for version in OLD_TLS {
+        let was = previous.tls_versions.split(',').any(|v| v == version);
+        let is = current.tls_versions.split(',').any(|v| v == version);
+        if is && !was {
+            issues.push(format!("{} is enabled again", version));
+        }
+    }
+
+    issues
+}
+
+pub async fn check_security(db: Pool<Postgres>) {
+    loop {
+        let today = Utc::now().checked_sub_days(Days::new(1));
+
+        let websites = sqlx::query!(
+            r#"select ws.id, ws.url
+            from website ws
+            where ws.is_paused = false and
+                (last_audit_at is null or last_audit_at <= $1) "#,
+            today
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+
+        for ws in websites.iter() {
+            // unreachable websites wait for the next day like the others
+            sqlx::query!(
+                r#"update website set last_audit_at = $2 where id = $1"#,
+                ws.id,
+                Utc::now()
+            )
+            .execute(&db)
+            .await
+            .unwrap();
+
+            let Some(opts) = checker::check_options(&db, ws.id).await else {
+                continue;
+            };
+            let Some((mut current, tls_checked)) = audit(&opts).await else {
+                println!("{} audit error", ws.url);
+                continue;
+            };
+
+            let previous = sqlx::query_as!(
+                Audit,
+                r#"select hsts, csp, x_frame_options, x_content_type_options, cookie_flags, tls_versions
+                from security_audit where website_id = $1
+                order by created_at desc limit 1"#,
+                ws.id
+            )
+            .fetch_optional(&db)
+            .await
+            .unwrap();
+
+            // a failed nmap run keeps the previous versions instead of comparing against none
+            if !tls_checked {
+                if let Some(previous) = &previous {
+                    current.tls_versions = previous.tls_versions.clone();
+                }
+            }
+
+            let issues = previous
+                .map(|previous| issues(&previous, &current))
+                .unwrap_or_default();
+
+            sqlx::query!(
+                r#"insert into security_audit
+                    (website_id, hsts, csp, x_frame_options, x_content_type_options, cookie_flags, tls_versions, issues)
+                values ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+                ws.id,
+                current.hsts,
+                current.csp,
+                current.x_frame_options,
+                current.x_content_type_options,
+                current.cookie_flags,
+                current.tls_versions,
+                issues.join("\n")
+            )
+            .execute(&db)
+            .await
+            .unwrap();
+
+            if !issues.is_empty() {
+                notification::notify(
+                    &db,
+                    Event {
+                        kind: EventKind::Audit,
+                        website_id: ws.id,
+                        url: ws.url.clone(),
+                        old_state: "".to_string(),
+                        new_state: "".to_string(),
+                        reason: Some(issues.join(", ")),
+                        duration: 0,
+                        downtime: None,
+                        at: Utc::now(),
+                        ack_link: None,
+                        expiry: None,
+                    },
+                )
+                .await;
+            }
+
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+}
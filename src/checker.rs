@@ -1,10 +1,21 @@
-use crate::appconfig::ENV;
+use crate::page_weight::{self, WeightLimits};
+use crate::notification::{self, Event, EventKind};
+use crate::{
+    appconfig::ENV, audit, crawler, crypto, dependency, digest_auth, escalation, expiry, flapping,
+    incident, maintenance, reminder, uptime,
+};
 use chrono::{DateTime, Days, Utc};
 use futures::{stream, StreamExt};
 use once_cell::sync::Lazy;
 use reqwest;
+use reqwest::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::{Method, Url};
 use scraper::{Html, Selector};
 use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::{process::Stdio, time::Duration};
 
 use execute::Execute;
@@ -18,48 +29,350 @@ pub static HTTP: Lazy<reqwest::Client> = Lazy::new(|| {
         .unwrap()
 });
 
-pub async fn check_state(url: String, keyword: String, useragent: Option<String>) -> (String, i64) {
-    let useragent = match useragent {
+// binding to an unspecified local address of one family makes the connector
+// skip resolved addresses of the other family
+pub static HTTP_V4: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .tcp_keepalive(None)
+        .local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+        .build()
+        .unwrap()
+});
+
+pub static HTTP_V6: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .tcp_keepalive(None)
+        .local_address(IpAddr::V6(Ipv6Addr::UNSPECIFIED))
+        .build()
+        .unwrap()
+});
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum IpFamily {
+    #[default]
+    Any,
+    V4,
+    V6,
+    Both,
+}
+
+impl IpFamily {
+    pub fn new(value: &str) -> IpFamily {
+        match value {
+            "ipv4" => IpFamily::V4,
+            "ipv6" => IpFamily::V6,
+            "both" => IpFamily::Both,
+            _ => IpFamily::Any,
+        }
+    }
+
+    fn client(&self) -> &'static reqwest::Client {
+        match self {
+            IpFamily::V4 => &HTTP_V4,
+            IpFamily::V6 => &HTTP_V6,
+            _ => &HTTP,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct CheckResult {
+    pub state: String,
+    pub duration: i64,
+    pub reason: Option<String>,
+}
+
+#[derive(Clone)]
+pub enum HttpAuth {
+    Basic { username: String, password: String },
+    Digest { username: String, password: String },
+}
+
+impl HttpAuth {
+    pub fn new(
+        auth_type: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Option<HttpAuth> {
+        let username = username.unwrap_or_default();
+        let password = password
+            .and_then(|p| crypto::decrypt(&p))
+            .unwrap_or_default();
+
+        match auth_type.as_deref() {
+            Some("basic") => Some(HttpAuth::Basic { username, password }),
+            Some("digest") => Some(HttpAuth::Digest { username, password }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct CheckOptions {
+    pub url: String,
+    pub keyword: String,
+    pub useragent: Option<String>,
+    pub auth: Option<HttpAuth>,
+    pub headers: Vec<(String, String)>,
+    pub ip_family: IpFamily,
+    pub weight: WeightLimits,
+}
+
+// parses "Name: value" lines and fills {{secret:NAME}} placeholders from the user's secret store
+pub fn parse_headers(raw: &str, secrets: &[(String, String)]) -> Vec<(String, String)> {
+    raw.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| {
+            let mut value = value.trim().to_string();
+            for (secret, secret_value) in secrets {
+                value = value.replace(&format!("{{{{secret:{}}}}}", secret), secret_value);
+            }
+            (name.trim().to_string(), value)
+        })
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+// credentials and custom headers only go to the monitored origin, not to CDNs or other hosts
+fn same_origin(monitored: &str, url: &str) -> bool {
+    match (Url::parse(monitored), Url::parse(url)) {
+        (Ok(monitored), Ok(url)) => monitored.origin() == url.origin(),
+        _ => false,
+    }
+}
+
+fn request(
+    http: &reqwest::Client,
+    opts: &CheckOptions,
+    method: Method,
+    url: &str,
+    useragent: &str,
+) -> reqwest::RequestBuilder {
+    let mut req = http
+        .request(method, url)
+        .header("User-Agent", useragent)
+        .timeout(Duration::from_secs(ENV.checker_timeout));
+
+    if same_origin(&opts.url, url) {
+        for (name, value) in opts.headers.iter() {
+            req = req.header(name.as_str(), value.as_str());
+        }
+    }
+
+    req
+}
+
+// a request with the monitor's auth and headers, also used by the audit, crawler and
+// page weight checks so protected websites aren't seen as 401 pages
+pub async fn send(
+    http: &reqwest::Client,
+    opts: &CheckOptions,
+    method: Method,
+    url: &str,
+    useragent: &str,
+) -> reqwest::Result<reqwest::Response> {
+    let auth = opts.auth.as_ref().filter(|_| same_origin(&opts.url, url));
+    match auth {
+        Some(HttpAuth::Basic { username, password }) => {
+            request(http, opts, method, url, useragent)
+                .basic_auth(username, Some(password))
+                .send()
+                .await
+        }
+        Some(HttpAuth::Digest { username, password }) => {
+            let resp = request(http, opts, method.clone(), url, useragent)
+                .send()
+                .await?;
+            if resp.status() != reqwest::StatusCode::UNAUTHORIZED {
+                return Ok(resp);
+            }
+
+            let url = resp.url().clone();
+            let uri = match url.query() {
+                Some(query) => format!("{}?{}", url.path(), query),
+                None => url.path().to_string(),
+            };
+            let authorization = resp
+                .headers()
+                .get(WWW_AUTHENTICATE)
+                .and_then(|h| h.to_str().ok())
+                .and_then(|challenge| {
+                    digest_auth::authorization(challenge, username, password, method.as_str(), &uri)
+                });
+
+            match authorization {
+                Some(authorization) => {
+                    request(http, opts, method, url, useragent)
+                        .header(AUTHORIZATION, authorization)
+                        .send()
+                        .await
+                }
+                None => Ok(resp),
+            }
+        }
+        None => request(http, opts, method, url, useragent).send().await,
+    }
+}
+
+pub fn useragent(opts: &CheckOptions) -> String {
+    match &opts.useragent {
         Some(ua) => format!("{} allgreen.me Up Time Checker", ua.clone() ),
         None => "Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/108.0.0.0 Safari/537.36 allgreen.me Up Time Checker".to_string()
-    };
+    }
+}
+
+async fn check_family(http: &reqwest::Client, opts: &CheckOptions) -> CheckResult {
+    let useragent = useragent(opts);
 
     let start = Utc::now().time();
-    let resp = HTTP
-        .get(&url)
-        .header("User-Agent", &useragent)
-        .timeout(Duration::from_secs(ENV.checker_timeout))
-        .send()
-        .await;
+    let resp = send(http, opts, Method::GET, &opts.url, &useragent).await;
 
     match resp {
         Ok(body) => {
+            let url = body.url().clone();
             let html = body.text().await;
-            let state = match html {
+            // the asset downloads of the weight check don't count as response time
+            let duration = (Utc::now().time() - start).num_milliseconds();
+            let (state, reason) = match html {
                 Ok(html) => {
-                    if html.contains(keyword.as_str()) {
-                        "up"
+                    if !html.contains(opts.keyword.as_str()) {
+                        ("down", Some("keyword not found".to_string()))
+                    } else if opts.weight.is_empty() {
+                        ("up", None)
                     } else {
-                        "down"
+                        match page_weight::check(http, opts, &useragent, &url, &html).await {
+                            Some(reason) => ("down", Some(reason)),
+                            None => ("up", None),
+                        }
                     }
                 }
-                Err(_) => "down",
+                Err(err) => ("down", Some(format!("can't read body: {}", err))),
             };
 
-            (
-                state.to_string(),
-                (Utc::now().time() - start).num_milliseconds(),
-            )
+            CheckResult {
+                state: state.to_string(),
+                duration,
+                reason,
+            }
         }
-        Err(_) => ("down".to_string(), 0),
+        Err(err) => CheckResult {
+            state: "down".to_string(),
+            duration: 0,
+            reason: Some(if err.is_timeout() {
+                "timeout".to_string()
+            } else {
+                format!("request failed: {}", err)
+            }),
+        },
+    }
+}
+
+pub async fn check_state(opts: &CheckOptions) -> CheckResult {
+    if opts.ip_family != IpFamily::Both {
+        return check_family(opts.ip_family.client(), opts).await;
+    }
+
+    let (v4, v6) = tokio::join!(
+        check_family(IpFamily::V4.client(), opts),
+        check_family(IpFamily::V6.client(), opts)
+    );
+
+    match (v4.state.as_str(), v6.state.as_str()) {
+        ("up", "up") => CheckResult {
+            duration: v4.duration.max(v6.duration),
+            ..v4
+        },
+        ("up", _) => CheckResult {
+            state: "degraded".to_string(),
+            duration: v4.duration,
+            reason: Some(format!("IPv6: {}", v6.reason.unwrap_or_default())),
+        },
+        (_, "up") => CheckResult {
+            state: "degraded".to_string(),
+            duration: v6.duration,
+            reason: Some(format!("IPv4: {}", v4.reason.unwrap_or_default())),
+        },
+        _ => CheckResult {
+            state: "down".to_string(),
+            duration: v4.duration.max(v6.duration),
+            reason: Some(format!(
+                "IPv4: {}, IPv6: {}",
+                v4.reason.unwrap_or_default(),
+                v6.reason.unwrap_or_default()
+            )),
+        },
+    }
+}
+
+// decrypted secrets of the user, only loaded when the headers use them
+async fn secrets(db: &Pool<Postgres>, user_id: Uuid, headers: &str) -> Vec<(String, String)> {
+    if !headers.contains("{{secret:") {
+        return vec![];
     }
+    sqlx::query!(
+        r#"select name, value from secret where user_id = $1"#,
+        user_id
+    )
+    .fetch_all(db)
+    .await
+    .unwrap_or_default()
+    .into_iter()
+    .filter_map(|s| crypto::decrypt(&s.value).map(|v| (s.name, v)))
+    .collect()
+}
+
+// auth and headers of a monitor for the audit and crawler requests
+pub async fn check_options(db: &Pool<Postgres>, website_id: Uuid) -> Option<CheckOptions> {
+    let ws = sqlx::query!(
+        r#"select url, keyword, useragent, user_id, auth_type, auth_username, auth_password,
+            headers, ip_family
+        from website where id = $1"#,
+        website_id
+    )
+    .fetch_optional(db)
+    .await
+    .ok()
+    .flatten()?;
+
+    let secrets = secrets(db, ws.user_id, &ws.headers).await;
+    Some(CheckOptions {
+        url: ws.url,
+        keyword: ws.keyword,
+        useragent: ws.useragent,
+        auth: HttpAuth::new(ws.auth_type, ws.auth_username, ws.auth_password),
+        headers: parse_headers(&ws.headers, &secrets),
+        ip_family: IpFamily::new(&ws.ip_family),
+        weight: WeightLimits::default(),
+    })
+}
+
+// start of the current run of down/degraded states, None when the website is up
+pub async fn down_since(db: &Pool<Postgres>, website_id: Uuid) -> Option<DateTime<Utc>> {
+    sqlx::query!(
+        r#"select min(created_at) as since from website_state
+        where website_id = $1 and state in ('down', 'degraded')
+            and created_at > coalesce(
+                (select max(created_at) from website_state
+                    where website_id = $1 and state not in ('down', 'degraded')),
+                '-infinity'::timestamptz)"#,
+        website_id
+    )
+    .fetch_one(db)
+    .await
+    .ok()
+    .and_then(|r| r.since)
 }
 
 pub async fn check_health(db: Pool<Postgres>) {
     loop {
         let websites = sqlx::query!(
-            r#"select ws.id, ws.keyword, ws.url, ws.useragent, u.discord_webhook,
-                last.state as state, ws.is_paused
+            r#"select ws.id, ws.keyword, ws.url, ws.useragent,
+                last.state as state, ws.is_paused, ws.user_id,
+                ws.auth_type, ws.auth_username, ws.auth_password, ws.headers, ws.ip_family,
+                ws.min_size, ws.max_size, ws.max_assets, ws.max_assets_size, ws.is_flapping,
+                ws.tags
             from website ws
             join (
                 select 
@@ -68,86 +381,205 @@ pub async fn check_health(db: Pool<Postgres>) {
                 from website_state
                 order by rn 
             ) as last on last.website_id = ws.id and last.rn = 1
-            join "user" u on u.id = ws.user_id
              "#
         )
         .fetch_all(&db)
         .await
         .unwrap();
 
-        stream::iter(websites)
-            .map(|ws| {
-                let client = db.clone();
-                tokio::spawn(async move {
-                    if ws.is_paused {
-                        if (ws.is_paused && ws.state == "paused") {
+        let windows = Arc::new(maintenance::windows(&db).await);
+        let parents = Arc::new(dependency::parents(&db).await);
+        let states: Arc<Mutex<HashMap<Uuid, String>>> = Arc::new(Mutex::new(
+            websites.iter().map(|ws| (ws.id, ws.state.clone())).collect(),
+        ));
+        let levels = dependency::levels(websites, |ws| ws.id, &parents);
+
+        for level in levels {
+            stream::iter(level)
+                .map(|ws| {
+                    let client = db.clone();
+                    let windows = windows.clone();
+                    let parents = parents.clone();
+                    let states = states.clone();
+                    tokio::spawn(async move {
+                        if ws.is_paused {
+                            if (ws.is_paused && ws.state == "paused") {
+                                return;
+                            }
+                            sqlx::query!(
+                                r#"insert into website_state (website_id, state, duration)
+                                    values ($1, $2, $3)  "#,
+                                ws.id,
+                                "paused",
+                                0
+                            )
+                            .execute(&client)
+                            .await
+                            .unwrap();
+                            incident::resolve(&client, ws.id, "Monitoring paused").await;
                             return;
                         }
-                        sqlx::query!(
-                            r#"insert into website_state (website_id, state, duration)
-                                values ($1, $2, $3)  "#,
-                            ws.id,
-                            "paused",
-                            0
-                        )
-                        .execute(&client)
-                        .await
-                        .unwrap();
-                        return;
-                    }
-                    let (mut state, mut duration) =
-                        check_state(ws.url.clone(), ws.keyword.clone(), ws.useragent.clone()).await;
-
-                    if state == "down" {
-                        if duration > 10000 {
-                            //recheck  if duration > 10seconds
-                            (state, duration) =
-                                check_state(ws.url.clone(), ws.keyword, ws.useragent.clone()).await;
+                        let secrets = secrets(&client, ws.user_id, &ws.headers).await;
+
+                        let opts = CheckOptions {
+                            url: ws.url.clone(),
+                            keyword: ws.keyword.clone(),
+                            useragent: ws.useragent.clone(),
+                            auth: HttpAuth::new(
+                                ws.auth_type.clone(),
+                                ws.auth_username.clone(),
+                                ws.auth_password.clone(),
+                            ),
+                            headers: parse_headers(&ws.headers, &secrets),
+                            ip_family: IpFamily::new(&ws.ip_family),
+                            weight: WeightLimits {
+                                min_size: ws.min_size,
+                                max_size: ws.max_size,
+                                max_assets: ws.max_assets,
+                                max_assets_size: ws.max_assets_size,
+                            },
+                        };
+
+                        let mut result = check_state(&opts).await;
+
+                        if result.state != "up" {
+                            if result.duration > 10000 {
+                                //recheck  if duration > 10seconds
+                                result = check_state(&opts).await;
+                            }
+                        };
+                        let CheckResult { state, duration, reason } = result;
+                        if state == "up" || state == "degraded" {
+                            uptime::record_response(&client, ws.id, duration).await;
                         }
-                    };
-                    println!("0");
-                    if ws.state != state {
-                        println!("1");
-                        sqlx::query!(
-                            r#"insert into website_state (website_id, state, duration)
-                                values ($1, $2, $3)  "#,
-                            ws.id,
-                            state,
-                            duration
-                        )
-                        .execute(&client)
-                        .await
-                        .unwrap();
-                        println!("2");
-                        println!("{} {} {} {:?}", ws.url, ws.state, state, ws.discord_webhook);
-                        println!("test");
-                        match &ws.discord_webhook {
-                            Some(url) => {
-                                let url = url.clone();
-                                tokio::spawn(async move {
-                                    let resp = HTTP
-                                        .post(url)
-                                        .header("content-type", "application/json")
-                                        .body(format!(
-                                            r#"{{ "content" : " {} is {} "  }}"#,
-                                            ws.url, state
-                                        ))
-                                        .send()
-                                        .await;
-
-                                    if resp.is_err() {
-                                        println!("error at url discord: {}", ws.url.clone());
-                                    }
-                                });
+
+                        // checks inside a maintenance window are recorded but never alert
+                        let now = Utc::now();
+                        let window = windows
+                            .iter()
+                            .find(|w| w.covers(ws.id, ws.user_id, &ws.tags) && w.is_active(now));
+                        // a failing child of a down parent is not the root cause
+                        let parent_down = parents.get(&ws.id).map_or(false, |ps| {
+                            let states = states.lock().unwrap();
+                            ps.iter()
+                                .any(|p| states.get(p).map_or(false, |s| dependency::is_down(s)))
+                        });
+                        let (state, reason) = match window {
+                            Some(window) => ("maintenance".to_string(), Some(window.name.clone())),
+                            None if state != "up" && parent_down => {
+                                let reason = reason.map(|r| format!("a dependency is down, {}", r));
+                                ("dependency_down".to_string(), reason)
                             }
-                            None => {}
+                            None => (state, reason),
                         };
-                    }
+                        states.lock().unwrap().insert(ws.id, state.clone());
+                        // quiet states never alert when entered
+                        let quiet = |s: &str| s == "maintenance" || s == "dependency_down";
+                        println!("0");
+                        if ws.state != state {
+                            println!("1");
+                            let downtime = if state == "up" {
+                                down_since(&client, ws.id)
+                                    .await
+                                    .map(|since| (Utc::now() - since).num_seconds())
+                            } else {
+                                None
+                            };
+                            sqlx::query!(
+                                r#"insert into website_state (website_id, state, duration, reason)
+                                    values ($1, $2, $3, $4)  "#,
+                                ws.id,
+                                state,
+                                duration,
+                                reason
+                            )
+                            .execute(&client)
+                            .await
+                            .unwrap();
+                            println!("2");
+                            println!("{} {} {}", ws.url, ws.state, state);
+
+                            if quiet(&state) {
+                                return;
+                            }
+                            // a flapping monitor stays silent until it has been stable for a window
+                            if ws.is_flapping {
+                                return;
+                            }
+
+                            if quiet(&ws.state) && state == "up" {
+                                // only the recovery of an incident opened before the quiet state
+                                // alerts, so PagerDuty and Opsgenie close theirs too
+                                if !incident::resolve(&client, ws.id, "Recovered").await {
+                                    return;
+                                }
+                            } else {
+                                let count = flapping::transitions(&client, ws.id).await;
+                                if count >= flapping::THRESHOLD {
+                                    flapping::start(&client, ws.id, &ws.url, &state, count).await;
+                                    return;
+                                }
+
+                                if state == "up" {
+                                    incident::resolve(&client, ws.id, "Recovered").await;
+                                } else {
+                                    incident::open(&client, ws.id, &state, &reason).await;
+                                }
+                            }
+                            notification::notify(
+                                &client,
+                                Event {
+                                    kind: EventKind::State,
+                                    website_id: ws.id,
+                                    url: ws.url.clone(),
+                                    old_state: ws.state.clone(),
+                                    new_state: state,
+                                    reason,
+                                    duration,
+                                    downtime,
+                                    at: Utc::now(),
+                                    ack_link: None,
+                                    expiry: None,
+                                },
+                            )
+                            .await;
+                        } else if ws.is_flapping
+                            && flapping::transitions(&client, ws.id).await == 0
+                        {
+                            flapping::stop(&client, ws.id).await;
+                            // settled in a quiet state, the open incident waits for the recovery
+                            if quiet(&state) {
+                                return;
+                            }
+                            if state == "up" {
+                                incident::resolve(&client, ws.id, "Recovered").await;
+                            } else {
+                                incident::open(&client, ws.id, &state, &reason).await;
+                            }
+                            notification::notify(
+                                &client,
+                                Event {
+                                    kind: EventKind::State,
+                                    website_id: ws.id,
+                                    url: ws.url.clone(),
+                                    old_state: "flapping".to_string(),
+                                    new_state: state,
+                                    reason,
+                                    duration,
+                                    downtime: None,
+                                    at: Utc::now(),
+                                    ack_link: None,
+                                    expiry: None,
+                                },
+                            )
+                            .await;
+                        }
+                    })
                 })
-            })
-            .buffer_unordered(ENV.checker_batch)
-            .for_each(|_| async {})
-            .await;
+                .buffer_unordered(ENV.checker_batch)
+                .for_each(|_| async {})
+                .await;
+        }
 
         tokio::time::sleep(Duration::from_secs(ENV.checker_sleep)).await;
         // thread::sleep(time::Duration::from_secs(ENV.checker_sleep));
@@ -157,16 +589,18 @@ pub async fn check_health(db: Pool<Postgres>) {
 pub async fn check_domain(db: Pool<Postgres>) {
     loop {
         let today = Utc::now().checked_sub_days(Days::new(1));
+        let hour_ago = Some(Utc::now() - chrono::Duration::hours(1));
 
+        // failed lookups are retried hourly instead of on every loop
         let websites = sqlx::query!(
-            r#"select ws.id, ws.keyword, ws.url, ws.useragent, u.discord_webhook
+            r#"select ws.id, ws.keyword, ws.url, ws.useragent
             from website ws
-            join "user" u on u.id = ws.user_id
             where 
                 (last_domain_checked_at is null 
-                    or domain_expire_at is null 
+                    or (domain_expire_at is null and last_domain_checked_at <= $2)
                     or  last_domain_checked_at <= $1) "#,
-            today
+            today,
+            hour_ago
         )
         .fetch_all(&db)
         .await
@@ -187,10 +621,19 @@ pub async fn check_domain(db: Pool<Postgres>) {
                 .send()
                 .await;
             let Ok(body) = resp else {
+                sqlx::query!(
+                    r#"update website set last_domain_checked_at = $2 where id = $1"#,
+                    ws.id,
+                    Some(Utc::now())
+                )
+                .execute(&db)
+                .await
+                .unwrap();
+                expiry::record_lookup(&db, ws.id, &ws.url, "domain", false).await;
                 continue;
             };
 
-            let html = body.text().await.unwrap();
+            let html = body.text().await.unwrap_or_default();
             let expire_at = {
                 let document = Html::parse_document(&html);
 
@@ -248,6 +691,7 @@ pub async fn check_domain(db: Pool<Postgres>) {
             .execute(&db)
             .await
             .unwrap();
+            expiry::record_lookup(&db, ws.id, &ws.url, "domain", expire_at.is_some()).await;
             tokio::time::sleep(Duration::from_secs(15)).await;
         }
 
@@ -258,22 +702,32 @@ pub async fn check_domain(db: Pool<Postgres>) {
 pub async fn check_ssl(db: Pool<Postgres>) {
     loop {
         let today = Utc::now().checked_sub_days(Days::new(1));
+        let hour_ago = Some(Utc::now() - chrono::Duration::hours(1));
 
         let websites = sqlx::query!(
-            r#"select ws.id, ws.keyword, ws.url, ws.useragent, u.discord_webhook
+            r#"select ws.id, ws.keyword, ws.url, ws.useragent
             from website ws
-            join "user" u on u.id = ws.user_id
-            where 
-                (last_domain_checked_at is null 
-                    or ssl_expire_at is null 
+            where ws.url like 'https://%' and
+                (last_ssl_checked_at is null 
+                    or (ssl_expire_at is null and last_ssl_checked_at <= $2)
                     or last_ssl_checked_at <= $1) "#,
-            today
+            today,
+            hour_ago
         )
         .fetch_all(&db)
         .await
         .unwrap();
 
         for ws in websites.iter() {
+            sqlx::query!(
+                r#"update website set last_ssl_checked_at = $2 where id = $1"#,
+                ws.id,
+                Some(Utc::now())
+            )
+            .execute(&db)
+            .await
+            .unwrap();
+
             let mut cmd = Command::new("nmap");
 
             let url = ws.url.replace("https://", "");
@@ -290,10 +744,12 @@ pub async fn check_ssl(db: Pool<Postgres>) {
 
             let Ok(output) = cmd.execute_output() else {
                 println!("{} nmap error", url);
+                expiry::record_lookup(&db, ws.id, &ws.url, "ssl", false).await;
                 continue;
             };
             let Ok(output) = String::from_utf8(output.stdout) else {
                 println!("{} cant read output", url);
+                expiry::record_lookup(&db, ws.id, &ws.url, "ssl", false).await;
                 continue;
             };
 
@@ -303,6 +759,7 @@ pub async fn check_ssl(db: Pool<Postgres>) {
                 .collect();
             let Some(not_valid_after) = not_valid_after.first() else {
                 println!("{} cant read date", url);
+                expiry::record_lookup(&db, ws.id, &ws.url, "ssl", false).await;
                 continue;
             };
             let not_valid_after = not_valid_after.replace("| Not valid after:", "");
@@ -312,6 +769,7 @@ pub async fn check_ssl(db: Pool<Postgres>) {
                 DateTime::parse_from_str(&not_valid_after, "%Y-%m-%d %H:%M:%S %z");
             let Ok(expire_at) = not_valid_after else {
                 println!("{} cant parse date", url);
+                expiry::record_lookup(&db, ws.id, &ws.url, "ssl", false).await;
                 continue;
             };
 
@@ -323,6 +781,7 @@ pub async fn check_ssl(db: Pool<Postgres>) {
             .execute(&db)
             .await
             .unwrap();
+            expiry::record_lookup(&db, ws.id, &ws.url, "ssl", true).await;
             tokio::time::sleep(Duration::from_secs(5)).await;
         }
 
@@ -334,8 +793,20 @@ pub async fn run(db: Pool<Postgres>) {
     let db1 = db.clone();
     let db2 = db.clone();
     let db3 = db.clone();
+    let db4 = db.clone();
+    let db5 = db.clone();
+    let db6 = db.clone();
+    let db7 = db.clone();
+    let db8 = db.clone();
+    let db9 = db.clone();
 
     tokio::spawn(async move { check_health(db1).await });
     tokio::spawn(async move { check_domain(db2).await });
     tokio::spawn(async move { check_ssl(db3).await });
+    tokio::spawn(async move { audit::check_security(db4).await });
+    tokio::spawn(async move { crawler::check_crawl(db5).await });
+    tokio::spawn(async move { notification::delivery::retry_failed(db6).await });
+    tokio::spawn(async move { escalation::escalate(db7).await });
+    tokio::spawn(async move { reminder::remind(db8).await });
+    tokio::spawn(async move { expiry::check_expiry(db9).await });
 }
@@ -0,0 +1,267 @@
+use crate::checker::{self, CheckOptions, HTTP};
+use chrono::{Days, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{Method, Url};
+use scraper::{Html, Selector};
+use sqlx::{Pool, Postgres};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+// minimum time between two crawler requests to the same host
+const HOST_INTERVAL: Duration = Duration::from_millis(1000);
+const MAX_PAGES: usize = 200;
+
+static LOC: Lazy<Regex> = Lazy::new(|| Regex::new(r"<loc>\s*([^<]+?)\s*</loc>").unwrap());
+static LAST_REQUEST: Lazy<Mutex<HashMap<String, Instant>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub struct CrawlIssue {
+    pub url: String,
+    pub status: Option<i32>,
+    pub error: Option<String>,
+}
+
+// reserves the next free slot for the host and waits for it
+async fn wait_turn(url: &Url) {
+    let host = url.host_str().unwrap_or("").to_string();
+    let wait = {
+        let mut last = LAST_REQUEST.lock().await;
+        let now = Instant::now();
+        let next = match last.get(&host) {
+            Some(at) if *at + HOST_INTERVAL > now => *at + HOST_INTERVAL,
+            _ => now,
+        };
+        last.insert(host, next);
+        next - now
+    };
+
+    tokio::time::sleep(wait).await;
+}
+
+async fn fetch(opts: &CheckOptions, url: &Url) -> Result<reqwest::Response, reqwest::Error> {
+    wait_turn(url).await;
+    checker::send(
+        &HTTP,
+        opts,
+        Method::GET,
+        url.as_str(),
+        "allgreen.me Link Checker",
+    )
+    .await
+}
+
+async fn sitemap(opts: &CheckOptions, base: &Url) -> Vec<Url> {
+    let Ok(sitemap) = base.join("/sitemap.xml") else {
+        return vec![];
+    };
+
+    let mut pending = vec![sitemap];
+    let mut pages = vec![];
+    let mut fetched = 0;
+
+    // sitemap indexes point to other sitemaps, follow them a few times
+    while let Some(sitemap) = pending.pop() {
+        fetched += 1;
+        if fetched > 10 {
+            break;
+        }
+        let Ok(resp) = fetch(opts, &sitemap).await else {
+            continue;
+        };
+        if !resp.status().is_success() {
+            continue;
+        }
+        let Ok(body) = resp.text().await else {
+            continue;
+        };
+
+        for loc in LOC.captures_iter(&body) {
+            let Ok(url) = Url::parse(&loc[1]) else {
+                continue;
+            };
+            if url.origin() != base.origin() {
+                continue;
+            }
+            if url.path().ends_with(".xml") {
+                pending.push(url);
+            } else {
+                pages.push(url);
+            }
+        }
+    }
+
+    pages.truncate(MAX_PAGES);
+    pages
+}
+
+fn links(base: &Url, html: &str) -> Vec<Url> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href]").unwrap();
+
+    document
+        .select(&selector)
+        .filter_map(|a| a.value().attr("href"))
+        .filter_map(|href| base.join(href).ok())
+        .filter(|url| url.origin() == base.origin())
+        .map(|mut url| {
+            url.set_fragment(None);
+            url
+        })
+        .collect()
+}
+
+fn issue(url: &Url, resp: &Result<reqwest::Response, reqwest::Error>) -> Option<CrawlIssue> {
+    match resp {
+        Ok(resp) if resp.status().is_client_error() || resp.status().is_server_error() => {
+            Some(CrawlIssue {
+                url: url.to_string(),
+                status: Some(resp.status().as_u16() as i32),
+                error: None,
+            })
+        }
+        Ok(_) => None,
+        Err(err) => Some(CrawlIssue {
+            url: url.to_string(),
+            status: err.status().map(|s| s.as_u16() as i32),
+            error: Some(if err.is_timeout() {
+                "timeout".to_string()
+            } else {
+                err.to_string()
+            }),
+        }),
+    }
+}
+
+// checks the pages listed in sitemap.xml, or follows same-origin links up to `depth`
+pub async fn crawl(opts: &CheckOptions, depth: i32) -> (usize, Vec<CrawlIssue>) {
+    let Ok(base) = Url::parse(&opts.url) else {
+        return (0, vec![]);
+    };
+
+    let mut issues = vec![];
+    let pages = sitemap(opts, &base).await;
+
+    if !pages.is_empty() {
+        for page in pages.iter() {
+            let resp = fetch(opts, page).await;
+            issues.extend(issue(page, &resp));
+        }
+        return (pages.len(), issues);
+    }
+
+    let mut seen = HashSet::from([base.clone()]);
+    let mut queue = VecDeque::from([(base, 0)]);
+    let mut checked = 0;
+
+    while let Some((page, level)) = queue.pop_front() {
+        if checked >= MAX_PAGES {
+            break;
+        }
+        checked += 1;
+
+        let resp = fetch(opts, &page).await;
+        if let Some(issue) = issue(&page, &resp) {
+            issues.push(issue);
+            continue;
+        }
+
+        if level >= depth {
+            continue;
+        }
+
+        let Ok(resp) = resp else {
+            continue;
+        };
+        let is_html = resp
+            .headers()
+            .get("content-type")
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.contains("text/html"))
+            .unwrap_or(false);
+        if !is_html {
+            continue;
+        }
+        let Ok(html) = resp.text().await else {
+            continue;
+        };
+
+        for link in links(&page, &html) {
+            if seen.insert(link.clone()) {
+                queue.push_back((link, level + 1));
+            }
+        }
+    }
+
+    (checked, issues)
+}
+
+pub async fn check_crawl(db: Pool<Postgres>) {
+    loop {
+        let today = Utc::now().checked_sub_days(Days::new(1));
+
+        let websites = sqlx::query!(
+            r#"select id, crawl_depth
+            from website
+            where crawl_enabled = true and is_paused = false and
+                (last_crawled_at is null or last_crawled_at <= $1) "#,
+            today
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+
+        for ws in websites.iter() {
+            let Some(opts) = checker::check_options(&db, ws.id).await else {
+                continue;
+            };
+
+            let run = sqlx::query!(
+                r#"insert into crawl_run (website_id) values ($1) returning id"#,
+                ws.id
+            )
+            .fetch_one(&db)
+            .await
+            .unwrap();
+
+            let (pages, issues) = crawl(&opts, ws.crawl_depth).await;
+
+            for issue in issues.iter() {
+                sqlx::query!(
+                    r#"insert into crawl_issue (crawl_run_id, url, status, error)
+                    values ($1, $2, $3, $4)"#,
+                    run.id,
+                    issue.url,
+                    issue.status,
+                    issue.error
+                )
+                .execute(&db)
+                .await
+                .unwrap();
+            }
+
+            sqlx::query!(
+                r#"update crawl_run set finished_at = $2, pages = $3 where id = $1"#,
+                run.id,
+                Utc::now(),
+                pages as i32
+            )
+            .execute(&db)
+            .await
+            .unwrap();
+
+            sqlx::query!(
+                r#"update website set last_crawled_at = $2 where id = $1"#,
+                ws.id,
+                Utc::now()
+            )
+            .execute(&db)
+            .await
+            .unwrap();
+        }
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+}
@@ -0,0 +1,32 @@
+use crate::appconfig::ENV;
+use hmac::{Hmac, Mac};
+use magic_crypt::{new_magic_crypt, MagicCryptTrait};
+use sha2::Sha256;
+
+// secrets and monitor passwords are stored encrypted with the app secret
+pub fn encrypt(value: &str) -> String {
+    let mc = new_magic_crypt!(&ENV.secret, 256);
+    mc.encrypt_str_to_base64(value)
+}
+
+pub fn decrypt(value: &str) -> Option<String> {
+    let mc = new_magic_crypt!(&ENV.secret, 256);
+    mc.decrypt_base64_to_string(value).ok()
+}
+
+// hex encoded HMAC-SHA256 of the payload
+pub fn sign(key: &str, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// constant time check of a signature made with sign
+pub fn verify(key: &str, payload: &str, signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
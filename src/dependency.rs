@@ -0,0 +1,130 @@
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub type Parents = HashMap<Uuid, Vec<Uuid>>;
+
+// states that explain a failing child, so only the root cause alerts
+pub fn is_down(state: &str) -> bool {
+    state == "down" || state == "dependency_down"
+}
+
+pub async fn parents(db: &Pool<Postgres>) -> Parents {
+    let mut parents = Parents::new();
+    let edges = sqlx::query!(r#"select website_id, parent_id from website_dependency"#)
+        .fetch_all(db)
+        .await
+        .unwrap_or_default();
+    for edge in edges {
+        parents.entry(edge.website_id).or_default().push(edge.parent_id);
+    }
+    parents
+}
+
+fn depth(
+    id: Uuid,
+    parents: &Parents,
+    depths: &mut HashMap<Uuid, usize>,
+    path: &mut Vec<Uuid>,
+) -> usize {
+    if let Some(depth) = depths.get(&id) {
+        return *depth;
+    }
+    // the UI refuses cycles, this only keeps a bad row from recursing forever
+    if path.contains(&id) {
+        return 0;
+    }
+    path.push(id);
+    let depth = parents
+        .get(&id)
+        .map(|ps| ps.iter().map(|p| depth(*p, parents, depths, path) + 1).max().unwrap_or(0))
+        .unwrap_or(0);
+    path.pop();
+    depths.insert(id, depth);
+    depth
+}
+
+// groups the monitors so every parent is checked in an earlier level than its children
+pub fn levels<T>(items: Vec<T>, id: impl Fn(&T) -> Uuid, parents: &Parents) -> Vec<Vec<T>> {
+    let mut depths = HashMap::new();
+    let mut levels: Vec<Vec<T>> = vec![];
+    for item in items {
+        let depth = depth(id(&item), parents, &mut depths, &mut vec![]);
+        while levels.len() <= depth {
+            levels.push(vec![]);
+        }
+        levels[depth].push(item);
+    }
+    levels.retain(|l| !l.is_empty());
+    levels
+}
+
+// a new parent closes a cycle when it is the website itself or depends on it
+pub fn would_cycle(website_id: Uuid, parent_id: Uuid, parents: &Parents) -> bool {
+    let mut stack = vec![parent_id];
+    let mut seen = vec![];
+    while let Some(id) = stack.pop() {
+        if id == website_id {
+            return true;
+        }
+        if seen.contains(&id) {
+            continue;
+        }
+        seen.push(id);
+        stack.extend(parents.get(&id).cloned().unwrap_or_default());
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    fn parents(edges: &[(u128, u128)]) -> Parents {
+        let mut parents = Parents::new();
+        for (website, parent) in edges {
+            parents.entry(id(*website)).or_default().push(id(*parent));
+        }
+        parents
+    }
+
+    #[test]
+    fn parents_come_in_earlier_levels() {
+        // 2 and 5 depend on 1, 3 on 2, 4 on both 1 and 3
+        let parents = parents(&[(2, 1), (3, 2), (4, 1), (4, 3), (5, 1)]);
+        let items = vec![id(4), id(3), id(6), id(2), id(1), id(5)];
+
+        let levels = levels(items, |i| *i, &parents);
+        assert_eq!(
+            levels,
+            vec![
+                vec![id(6), id(1)],
+                vec![id(2), id(5)],
+                vec![id(3)],
+                vec![id(4)],
+            ]
+        );
+    }
+
+    #[test]
+    fn levels_survive_a_cycle() {
+        let parents = parents(&[(1, 2), (2, 1)]);
+        let levels = levels(vec![id(1), id(2)], |i| *i, &parents);
+        assert_eq!(levels.iter().map(|l| l.len()).sum::<usize>(), 2);
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let parents = parents(&[(2, 1), (3, 2), (4, 1)]);
+        assert!(would_cycle(id(1), id(1), &parents));
+        assert!(would_cycle(id(1), id(2), &parents));
+        assert!(would_cycle(id(1), id(3), &parents));
+        assert!(!would_cycle(id(3), id(4), &parents));
+        assert!(!would_cycle(id(4), id(3), &parents));
+        assert!(!would_cycle(id(1), id(5), &parents));
+    }
+}
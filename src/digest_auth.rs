@@ -0,0 +1,72 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+static PARAM: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(\w+)\s*=\s*(?:"([^"]*)"|([^,\s]*))"#).unwrap());
+
+fn md5_hex(value: String) -> String {
+    format!("{:x}", md5::compute(value))
+}
+
+// builds the Authorization header for a `WWW-Authenticate: Digest ...` challenge (RFC 7616, MD5 only)
+pub fn authorization(
+    challenge: &str,
+    username: &str,
+    password: &str,
+    method: &str,
+    uri: &str,
+) -> Option<String> {
+    let challenge = challenge.trim();
+    if !challenge.to_lowercase().starts_with("digest") {
+        return None;
+    }
+
+    let params: HashMap<String, String> = PARAM
+        .captures_iter(&challenge[6..])
+        .map(|c| {
+            let value = c.get(2).or(c.get(3)).map(|v| v.as_str()).unwrap_or("");
+            (c[1].to_lowercase(), value.to_string())
+        })
+        .collect();
+
+    let realm = params.get("realm")?;
+    let nonce = params.get("nonce")?;
+    let algorithm = params.get("algorithm").map(|a| a.as_str()).unwrap_or("MD5");
+    if !algorithm.eq_ignore_ascii_case("md5") {
+        return None;
+    }
+
+    let ha1 = md5_hex(format!("{}:{}:{}", username, realm, password));
+    let ha2 = md5_hex(format!("{}:{}", method, uri));
+
+    let qop = params
+        .get("qop")
+        .map(|q| q.split(',').any(|q| q.trim() == "auth"))
+        .unwrap_or(false);
+
+    let mut header = format!(
+        r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", algorithm=MD5"#,
+        username, realm, nonce, uri
+    );
+
+    if qop {
+        let cnonce = Uuid::new_v4().simple().to_string();
+        let nc = "00000001";
+        let response = md5_hex(format!("{}:{}:{}:{}:auth:{}", ha1, nonce, nc, cnonce, ha2));
+        header.push_str(&format!(
+            r#", qop=auth, nc={}, cnonce="{}", response="{}""#,
+            nc, cnonce, response
+        ));
+    } else {
+        let response = md5_hex(format!("{}:{}:{}", ha1, nonce, ha2));
+        header.push_str(&format!(r#", response="{}""#, response));
+    }
+
+    if let Some(opaque) = params.get("opaque") {
+        header.push_str(&format!(r#", opaque="{}""#, opaque));
+    }
+
+    Some(header)
+}
@@ -0,0 +1,88 @@
+use crate::notification::{delivery, Channel, Event, EventKind};
+use chrono::Utc;
+use sqlx::{Pool, Postgres};
+use std::time::Duration;
+
+pub async fn escalate(db: Pool<Postgres>) {
+    loop {
+        let incidents = sqlx::query!(
+            r#"select i.id, i.website_id, i.opened_at, i.escalation_step, ws.url,
+                ws.escalation_policy_id as "policy_id!", last.state as "state!", last.reason
+            from incident i
+            join website ws on ws.id = i.website_id
+            join lateral (
+                select state, reason from website_state
+                where website_id = ws.id
+                order by created_at desc limit 1
+            ) as last on true
+            where i.resolved_at is null and i.acknowledged_at is null and not ws.is_flapping
+                and last.state not in ('maintenance', 'dependency_down')
+                and ws.escalation_policy_id is not null"#
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap_or_default();
+
+        for incident in incidents {
+            let steps = sqlx::query!(
+                r#"select es.delay_minutes, c.id, c.kind, c.name, c.config, u.email as owner_email
+                from escalation_step es
+                join notification_channel c on c.id = es.channel_id
+                join "user" u on u.id = c.user_id
+                where es.policy_id = $1
+                order by es.delay_minutes, es.id"#,
+                incident.policy_id
+            )
+            .fetch_all(&db)
+            .await
+            .unwrap_or_default();
+
+            let elapsed = (Utc::now() - incident.opened_at).num_seconds();
+            let mut reached = incident.escalation_step;
+
+            for step in steps.into_iter().skip(incident.escalation_step as usize) {
+                if step.delay_minutes as i64 * 60 > elapsed {
+                    break;
+                }
+                reached += 1;
+
+                let channel = Channel {
+                    id: step.id,
+                    kind: step.kind,
+                    name: step.name,
+                    config: step.config,
+                    owner_email: step.owner_email,
+                };
+                let event = Event {
+                    kind: EventKind::Escalation,
+                    website_id: incident.website_id,
+                    url: incident.url.clone(),
+                    old_state: incident.state.clone(),
+                    new_state: incident.state.clone(),
+                    reason: incident.reason.clone(),
+                    duration: 0,
+                    downtime: Some(elapsed),
+                    at: Utc::now(),
+                    ack_link: None,
+                    expiry: None,
+                };
+                let db = db.clone();
+                tokio::spawn(async move {
+                    delivery::deliver(&db, &channel, &event, 1).await;
+                });
+            }
+
+            if reached != incident.escalation_step {
+                let _ = sqlx::query!(
+                    r#"update incident set escalation_step = $2 where id = $1"#,
+                    incident.id,
+                    reached
+                )
+                .execute(&db)
+                .await;
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+}
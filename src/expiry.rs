@@ -0,0 +1,180 @@
+use crate::notification::{self, Event, EventKind};
+use chrono::{DateTime, Utc};
+use sqlx::{Pool, Postgres};
+use std::time::Duration;
+use uuid::Uuid;
+
+pub const DEFAULT_THRESHOLDS: &str = "30,14,7,1";
+// consecutive failed lookups before the owner is told expiry can't be tracked
+pub const FAILURE_LIMIT: i32 = 3;
+
+// "30, 14,7" to [30, 14, 7], largest first
+pub fn thresholds(value: &str) -> Vec<i64> {
+    let mut days: Vec<i64> = value
+        .split(',')
+        .filter_map(|d| d.trim().parse::<i64>().ok())
+        .filter(|d| *d >= 0)
+        .collect();
+    days.sort_unstable_by(|a, b| b.cmp(a));
+    days.dedup();
+    days
+}
+
+pub fn subject(kind: &str) -> &'static str {
+    match kind {
+        "ssl" => "SSL certificate",
+        _ => "domain",
+    }
+}
+
+// expiry alerts don't change the state, both states are the current one
+async fn event(
+    db: &Pool<Postgres>,
+    website_id: Uuid,
+    url: &str,
+    kind: &str,
+    reason: String,
+) -> Event {
+    let state = sqlx::query!(
+        r#"select state from website_state where website_id = $1
+        order by created_at desc limit 1"#,
+        website_id
+    )
+    .fetch_optional(db)
+    .await
+    .ok()
+    .flatten()
+    .map(|s| s.state)
+    .unwrap_or_default();
+
+    Event {
+        kind: EventKind::Expiry,
+        website_id,
+        url: url.to_string(),
+        old_state: state.clone(),
+        new_state: state,
+        reason: Some(reason),
+        duration: 0,
+        downtime: None,
+        at: Utc::now(),
+        ack_link: None,
+        expiry: Some(kind.to_string()),
+    }
+}
+
+// counts consecutive failed domain/ssl lookups and alerts once when the limit is reached
+pub async fn record_lookup(
+    db: &Pool<Postgres>,
+    website_id: Uuid,
+    url: &str,
+    kind: &str,
+    ok: bool,
+) {
+    let failures = sqlx::query!(
+        r#"update website set
+            domain_check_failures = case when $2 = 'domain'
+                then (case when $3 then 0 else domain_check_failures + 1 end)
+                else domain_check_failures end,
+            ssl_check_failures = case when $2 = 'ssl'
+                then (case when $3 then 0 else ssl_check_failures + 1 end)
+                else ssl_check_failures end
+        where id = $1
+        returning case when $2 = 'ssl' then ssl_check_failures
+            else domain_check_failures end as "failures!""#,
+        website_id,
+        kind,
+        ok
+    )
+    .fetch_one(db)
+    .await;
+
+    if let Ok(row) = failures {
+        if row.failures == FAILURE_LIMIT {
+            let reason = format!(
+                "{} expiry lookup failed {} times in a row",
+                subject(kind),
+                FAILURE_LIMIT
+            );
+            notification::notify(db, event(db, website_id, url, kind, reason).await).await;
+        }
+    }
+}
+
+async fn alert(
+    db: &Pool<Postgres>,
+    website_id: Uuid,
+    url: &str,
+    kind: &str,
+    expire_at: DateTime<Utc>,
+    thresholds: &[i64],
+) {
+    let days_left = (expire_at - Utc::now()).num_days();
+
+    // every threshold fires once per expiry date, a renewal arms them again
+    let crossed: Vec<i64> = thresholds.iter().filter(|t| days_left <= **t).copied().collect();
+    let Some(lowest) = crossed.last() else {
+        return;
+    };
+
+    let fired = sqlx::query!(
+        r#"select threshold from expiry_alert
+        where website_id = $1 and kind = $2 and expire_at = $3"#,
+        website_id,
+        kind,
+        expire_at
+    )
+    .fetch_all(db)
+    .await
+    .unwrap_or_default();
+
+    if fired.iter().any(|f| f.threshold as i64 == *lowest) {
+        return;
+    }
+
+    for threshold in crossed.iter() {
+        let _ = sqlx::query!(
+            r#"insert into expiry_alert (website_id, kind, expire_at, threshold)
+            values ($1, $2, $3, $4) on conflict do nothing"#,
+            website_id,
+            kind,
+            expire_at,
+            *threshold as i32
+        )
+        .execute(db)
+        .await;
+    }
+
+    let reason = if days_left < 0 {
+        format!("expired on {}", expire_at.format("%d/%m/%Y"))
+    } else {
+        format!("expires in {} days on {}", days_left, expire_at.format("%d/%m/%Y"))
+    };
+    notification::notify(db, event(db, website_id, url, kind, reason).await).await;
+}
+
+pub async fn check_expiry(db: Pool<Postgres>) {
+    loop {
+        let websites = sqlx::query!(
+            r#"select ws.id, ws.url, ws.domain_expire_at, ws.ssl_expire_at, u.expiry_thresholds
+            from website ws
+            join "user" u on u.id = ws.user_id
+            where not ws.is_paused
+                and (ws.domain_expire_at is not null or ws.ssl_expire_at is not null)"#
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap_or_default();
+
+        for ws in websites {
+            let thresholds = thresholds(&ws.expiry_thresholds);
+            if let Some(expire_at) = ws.domain_expire_at {
+                alert(&db, ws.id, &ws.url, "domain", expire_at, &thresholds).await;
+            }
+            if let Some(expire_at) = ws.ssl_expire_at {
+                alert(&db, ws.id, &ws.url, "ssl", expire_at, &thresholds).await;
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+    }
+}
@@ -0,0 +1,58 @@
+use crate::notification::{self, format_duration, Event, EventKind};
+use chrono::Utc;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+// a monitor changing state this many times within the window is flapping,
+// it is stable again after a full window without changes
+pub const WINDOW_MINUTES: i64 = 30;
+pub const THRESHOLD: i64 = 5;
+
+pub async fn transitions(db: &Pool<Postgres>, website_id: Uuid) -> i64 {
+    sqlx::query!(
+        r#"select count(*) as "count!" from website_state
+        where website_id = $1 and state in ('up', 'down', 'degraded')
+            and created_at > now() - make_interval(mins => $2)"#,
+        website_id,
+        WINDOW_MINUTES as i32
+    )
+    .fetch_one(db)
+    .await
+    .map(|r| r.count)
+    .unwrap_or(0)
+}
+
+// marks the monitor flapping and sends the only alert until it settles
+pub async fn start(db: &Pool<Postgres>, website_id: Uuid, url: &str, state: &str, count: i64) {
+    let _ = sqlx::query!(r#"update website set is_flapping = true where id = $1"#, website_id)
+        .execute(db)
+        .await;
+
+    notification::notify(
+        db,
+        Event {
+            kind: EventKind::Flapping,
+            website_id,
+            url: url.to_string(),
+            old_state: state.to_string(),
+            new_state: "flapping".to_string(),
+            reason: Some(format!(
+                "{} state changes in {}, alerts are paused until it is stable",
+                count,
+                format_duration(WINDOW_MINUTES * 60)
+            )),
+            duration: 0,
+            downtime: None,
+            at: Utc::now(),
+            ack_link: None,
+            expiry: None,
+        },
+    )
+    .await;
+}
+
+pub async fn stop(db: &Pool<Postgres>, website_id: Uuid) {
+    let _ = sqlx::query!(r#"update website set is_flapping = false where id = $1"#, website_id)
+        .execute(db)
+        .await;
+}
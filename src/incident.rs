@@ -0,0 +1,212 @@
+use crate::appconfig::ENV;
+use crate::crypto;
+use crate::notification::format_duration;
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+pub const ACK_LINK_HOURS: i64 = 24;
+
+pub async fn record(
+    db: &Pool<Postgres>,
+    incident_id: Uuid,
+    kind: &str,
+    message: &str,
+    author: Option<&str>,
+) {
+    let _ = sqlx::query!(
+        r#"insert into incident_event (incident_id, kind, message, author)
+        values ($1, $2, $3, $4)"#,
+        incident_id,
+        kind,
+        message,
+        author
+    )
+    .execute(db)
+    .await;
+}
+
+// adds to the latest incident of the website, if it has one
+pub async fn record_latest(db: &Pool<Postgres>, website_id: Uuid, kind: &str, message: &str) {
+    let incident = sqlx::query!(
+        r#"select id from incident where website_id = $1 order by opened_at desc limit 1"#,
+        website_id
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None);
+
+    if let Some(incident) = incident {
+        record(db, incident.id, kind, message, None).await;
+    }
+}
+
+fn describe(state: &str, reason: &Option<String>) -> String {
+    match reason {
+        Some(reason) => format!("Checked {}: {}", state, reason),
+        None => format!("Checked {}", state),
+    }
+}
+
+// opens an incident when a website goes down, later failed checks join the open one.
+// escalation steps without delay are already covered by the state change notification
+pub async fn open(db: &Pool<Postgres>, website_id: Uuid, state: &str, reason: &Option<String>) {
+    let opened = sqlx::query!(
+        r#"insert into incident (website_id, escalation_step)
+        select ws.id, (select count(*)::int from escalation_step es
+            where es.policy_id = ws.escalation_policy_id and es.delay_minutes = 0)
+        from website ws
+        where ws.id = $1 and not exists (
+            select 1 from incident i where i.website_id = ws.id and i.resolved_at is null)
+        returning id"#,
+        website_id
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None);
+
+    match opened {
+        Some(incident) => {
+            record(db, incident.id, "opened", &describe(state, reason), None).await;
+        }
+        None => record_latest(db, website_id, "check", &describe(state, reason)).await,
+    }
+}
+
+// false when there was no open incident to resolve
+pub async fn resolve(db: &Pool<Postgres>, website_id: Uuid, message: &str) -> bool {
+    let resolved = sqlx::query!(
+        r#"update incident set resolved_at = now()
+        where website_id = $1 and resolved_at is null
+        returning id, extract(epoch from resolved_at - opened_at)::bigint as "seconds!""#,
+        website_id
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None);
+
+    let Some(incident) = resolved else {
+        return false;
+    };
+    let message = format!("{} after {}", message, format_duration(incident.seconds));
+    record(db, incident.id, "resolved", &message, None).await;
+    true
+}
+
+// "<incident>.<channel>.<expires>.<signature>", signed with the app secret so no token
+// has to be stored, the channel tells who the link was sent to
+fn sign_ack_token(key: &str, incident_id: Uuid, channel_id: Uuid, expires: i64) -> String {
+    let payload = format!("{}.{}.{}", incident_id, channel_id, expires);
+    format!("{}.{}", payload, crypto::sign(key, &payload))
+}
+
+pub fn ack_token(incident_id: Uuid, channel_id: Uuid) -> String {
+    let expires = (Utc::now() + Duration::hours(ACK_LINK_HOURS)).timestamp();
+    sign_ack_token(&ENV.secret, incident_id, channel_id, expires)
+}
+
+pub fn ack_link(incident_id: Uuid, channel_id: Uuid) -> String {
+    format!("{}/incident/ack/{}", ENV.host, ack_token(incident_id, channel_id))
+}
+
+fn parse_ack_token(key: &str, token: &str, now: i64) -> Option<(Uuid, Uuid)> {
+    let parts: Vec<&str> = token.split('.').collect();
+    let [incident_id, channel_id, expires, signature] = parts[..] else {
+        return None;
+    };
+
+    let payload = format!("{}.{}.{}", incident_id, channel_id, expires);
+    if !crypto::verify(key, &payload, signature) {
+        return None;
+    }
+    if expires.parse::<i64>().ok()? < now {
+        return None;
+    }
+    Some((incident_id.parse().ok()?, channel_id.parse().ok()?))
+}
+
+// incident and channel of a valid, unexpired token
+pub fn verify_ack_token(token: &str) -> Option<(Uuid, Uuid)> {
+    parse_ack_token(&ENV.secret, token, Utc::now().timestamp())
+}
+
+// open incident that still escalates and reminds, the one an ack link points to
+pub async fn pending(db: &Pool<Postgres>, website_id: Uuid) -> Option<Uuid> {
+    sqlx::query!(
+        r#"select id from incident
+        where website_id = $1 and resolved_at is null and acknowledged_at is null"#,
+        website_id
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None)
+    .map(|i| i.id)
+}
+
+// stops escalation and reminders of the incident, false when there is nothing to acknowledge
+pub async fn acknowledge_incident(db: &Pool<Postgres>, incident_id: Uuid, by: &str) -> bool {
+    let acknowledged = sqlx::query!(
+        r#"update incident set acknowledged_at = now(), acknowledged_by = $2
+        where id = $1 and resolved_at is null and acknowledged_at is null"#,
+        incident_id,
+        by
+    )
+    .execute(db)
+    .await
+    .map(|r| r.rows_affected() > 0)
+    .unwrap_or(false);
+
+    if acknowledged {
+        record(db, incident_id, "acknowledged", "Acknowledged", Some(by)).await;
+    }
+    acknowledged
+}
+
+pub async fn acknowledge(db: &Pool<Postgres>, website_id: Uuid, by: &str) -> bool {
+    match pending(db, website_id).await {
+        Some(incident_id) => acknowledge_incident(db, incident_id, by).await,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "test secret";
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn ack_token_round_trip() {
+        let (incident, channel) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let token = sign_ack_token(KEY, incident, channel, NOW + 60);
+        assert_eq!(parse_ack_token(KEY, &token, NOW), Some((incident, channel)));
+    }
+
+    #[test]
+    fn rejects_expired_ack_token() {
+        let token = sign_ack_token(KEY, Uuid::from_u128(1), Uuid::from_u128(2), NOW - 1);
+        assert_eq!(parse_ack_token(KEY, &token, NOW), None);
+    }
+
+    #[test]
+    fn rejects_tampered_ack_token() {
+        let token = sign_ack_token(KEY, Uuid::from_u128(1), Uuid::from_u128(2), NOW + 60);
+        assert_eq!(parse_ack_token("other secret", &token, NOW), None);
+
+        let other = Uuid::from_u128(3).to_string();
+        let forged = token.replacen(&Uuid::from_u128(1).to_string(), &other, 1);
+        assert_eq!(parse_ack_token(KEY, &forged, NOW), None);
+
+        let extended = token.replacen(&(NOW + 60).to_string(), &(NOW + 3600).to_string(), 1);
+        assert_eq!(parse_ack_token(KEY, &extended, NOW), None);
+    }
+
+    #[test]
+    fn rejects_malformed_ack_token() {
+        assert_eq!(parse_ack_token(KEY, "", NOW), None);
+        assert_eq!(parse_ack_token(KEY, "a.b.c", NOW), None);
+        assert_eq!(parse_ack_token(KEY, "a.b.c.d.e", NOW), None);
+        assert_eq!(parse_ack_token(KEY, "a.b.c.zz", NOW), None);
+    }
+}
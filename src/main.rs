@@ -2,16 +2,33 @@ extern crate core;
 
 use crate::appconfig::ENV;
 use async_session::CookieStore;
-use axum::{extract::FromRef, http::StatusCode, response::IntoResponse, routing::*, Router};
+use axum::{
+    extract::FromRef, http::StatusCode, middleware, response::IntoResponse, routing::*, Router,
+};
 
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::io;
+use std::net::SocketAddr;
 use tower_http::services::ServeDir;
 mod app;
 mod appconfig;
+mod audit;
 mod checker;
+mod crawler;
+mod crypto;
+mod dependency;
+mod digest_auth;
+mod escalation;
+mod expiry;
+mod flapping;
+mod incident;
 mod logged_user;
+mod maintenance;
 mod models;
+mod notification;
+mod page_weight;
+mod reminder;
+mod uptime;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -52,11 +69,16 @@ async fn main() {
 
     let app = Router::new()
         .nest("/", app::router())
+        // custom status page domains are dispatched on the Host header before the app routes
+        .layer(middleware::from_fn_with_state(
+            app_state.db.clone(),
+            app::status_page::custom_domain,
+        ))
         .with_state(app_state)
         .fallback_service(serve_dir);
 
     axum::Server::bind(&ENV.addr.parse().unwrap())
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap();
 }
@@ -0,0 +1,275 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+// the supported RRULE subset: FREQ=DAILY|WEEKLY|MONTHLY, INTERVAL, BYDAY (weekly) and UNTIL
+pub struct Rule {
+    pub freq: Freq,
+    pub interval: i64,
+    pub by_day: Vec<Weekday>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+fn weekday(value: &str) -> Option<Weekday> {
+    match value {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn until(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Some(Utc.from_utc_datetime(&date));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+    Some(Utc.from_utc_datetime(&date.and_hms_opt(23, 59, 59)?))
+}
+
+pub fn parse(rrule: &str) -> Result<Rule, String> {
+    let mut rule = Rule {
+        freq: Freq::Daily,
+        interval: 1,
+        by_day: vec![],
+        until: None,
+    };
+    let mut freq = None;
+
+    let rrule = rrule.trim().trim_start_matches("RRULE:");
+    for part in rrule.split(';').filter(|p| !p.is_empty()) {
+        let Some((key, value)) = part.split_once('=') else {
+            return Err(format!("invalid rule part {}", part));
+        };
+        match key.trim().to_uppercase().as_str() {
+            "FREQ" => {
+                freq = match value.trim().to_uppercase().as_str() {
+                    "DAILY" => Some(Freq::Daily),
+                    "WEEKLY" => Some(Freq::Weekly),
+                    "MONTHLY" => Some(Freq::Monthly),
+                    _ => return Err(format!("unsupported frequency {}", value)),
+                }
+            }
+            "INTERVAL" => {
+                rule.interval = value
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|i| *i > 0)
+                    .ok_or(format!("invalid interval {}", value))?
+            }
+            "BYDAY" => {
+                for day in value.split(',') {
+                    let day = day.trim().to_uppercase();
+                    rule.by_day.push(weekday(&day).ok_or(format!("invalid day {}", day))?);
+                }
+            }
+            "UNTIL" => {
+                rule.until = Some(until(value.trim()).ok_or(format!("invalid until {}", value))?)
+            }
+            _ => return Err(format!("unsupported rule part {}", key)),
+        }
+    }
+
+    rule.freq = freq.ok_or("FREQ is required".to_string())?;
+    Ok(rule)
+}
+
+impl Rule {
+    // whether an occurrence starts on the day of the candidate, candidates keep the
+    // time of day of the first window
+    fn occurs(&self, first: DateTime<Utc>, candidate: DateTime<Utc>) -> bool {
+        let days = (candidate.date_naive() - first.date_naive()).num_days();
+        match self.freq {
+            Freq::Daily => days % self.interval == 0,
+            Freq::Weekly => {
+                let week_start = |d: DateTime<Utc>| {
+                    d.date_naive() - Duration::days(d.weekday().num_days_from_monday() as i64)
+                };
+                let weeks = (week_start(candidate) - week_start(first)).num_days() / 7;
+                let on_day = if self.by_day.is_empty() {
+                    candidate.weekday() == first.weekday()
+                } else {
+                    self.by_day.contains(&candidate.weekday())
+                };
+                on_day && weeks % self.interval == 0
+            }
+            Freq::Monthly => {
+                let months = (candidate.year() - first.year()) as i64 * 12
+                    + candidate.month() as i64
+                    - first.month() as i64;
+                candidate.day() == first.day() && months % self.interval == 0
+            }
+        }
+    }
+}
+
+pub fn is_active(
+    starts_at: DateTime<Utc>,
+    duration_minutes: i32,
+    rrule: Option<&str>,
+    now: DateTime<Utc>,
+) -> bool {
+    let duration = Duration::minutes(duration_minutes as i64);
+    if now < starts_at {
+        return false;
+    }
+
+    let Some(rule) = rrule.filter(|r| !r.trim().is_empty()).and_then(|r| parse(r).ok()) else {
+        return now < starts_at + duration;
+    };
+
+    // only the occurrences that started recently enough to still be running
+    let days = (now - starts_at).num_days();
+    let span = duration.num_days() + 1;
+    for day in (days - span).max(0)..=days {
+        let candidate = starts_at + Duration::days(day);
+        if rule.until.is_some_and(|until| candidate > until) {
+            continue;
+        }
+        if candidate <= now && now < candidate + duration && rule.occurs(starts_at, candidate) {
+            return true;
+        }
+    }
+    false
+}
+
+pub struct Window {
+    pub name: String,
+    pub user_id: Uuid,
+    pub website_id: Option<Uuid>,
+    pub tag: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub duration_minutes: i32,
+    pub rrule: Option<String>,
+}
+
+impl Window {
+    pub fn covers(&self, website_id: Uuid, user_id: Uuid, tags: &str) -> bool {
+        if self.user_id != user_id {
+            return false;
+        }
+        match (&self.website_id, &self.tag) {
+            (Some(id), _) => *id == website_id,
+            (None, Some(tag)) => tags
+                .split([',', ' '])
+                .any(|t| t.trim().eq_ignore_ascii_case(tag.trim())),
+            (None, None) => false,
+        }
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        is_active(self.starts_at, self.duration_minutes, self.rrule.as_deref(), now)
+    }
+}
+
+pub async fn windows(db: &Pool<Postgres>) -> Vec<Window> {
+    sqlx::query_as!(
+        Window,
+        r#"select name, user_id, website_id, tag, starts_at, duration_minutes, rrule
+        from maintenance_window"#
+    )
+    .fetch_all(db)
+    .await
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_weekly_rule() {
+        let rule = parse("RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=mo,WE").unwrap();
+        assert!(rule.freq == Freq::Weekly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.by_day, vec![Weekday::Mon, Weekday::Wed]);
+        assert_eq!(rule.until, None);
+    }
+
+    #[test]
+    fn parses_until() {
+        let rule = parse("FREQ=DAILY;UNTIL=20240131").unwrap();
+        assert_eq!(rule.until, Some(at("2024-01-31T23:59:59Z")));
+        let rule = parse("FREQ=DAILY;UNTIL=20240131T120000Z").unwrap();
+        assert_eq!(rule.until, Some(at("2024-01-31T12:00:00Z")));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(parse("INTERVAL=2").is_err());
+        assert!(parse("FREQ=YEARLY").is_err());
+        assert!(parse("FREQ=DAILY;INTERVAL=0").is_err());
+        assert!(parse("FREQ=WEEKLY;BYDAY=XX").is_err());
+        assert!(parse("FREQ=DAILY;COUNT=3").is_err());
+        assert!(parse("FREQ").is_err());
+    }
+
+    #[test]
+    fn one_off_window() {
+        let starts_at = at("2024-01-01T22:00:00Z");
+        assert!(!is_active(starts_at, 60, None, at("2024-01-01T21:59:00Z")));
+        assert!(is_active(starts_at, 60, None, at("2024-01-01T22:30:00Z")));
+        assert!(!is_active(starts_at, 60, None, at("2024-01-01T23:00:00Z")));
+        assert!(is_active(starts_at, 60, Some(" "), at("2024-01-01T22:30:00Z")));
+    }
+
+    #[test]
+    fn daily_window_across_midnight() {
+        let starts_at = at("2024-01-01T23:30:00Z");
+        let rrule = Some("FREQ=DAILY");
+        assert!(is_active(starts_at, 120, rrule, at("2024-01-05T00:30:00Z")));
+        assert!(!is_active(starts_at, 120, rrule, at("2024-01-05T02:00:00Z")));
+        assert!(!is_active(starts_at, 120, rrule, at("2024-01-05T12:00:00Z")));
+    }
+
+    #[test]
+    fn weekly_window_on_days() {
+        // 2024-01-01 is a monday
+        let starts_at = at("2024-01-01T02:00:00Z");
+        let rrule = Some("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE");
+        assert!(is_active(starts_at, 60, rrule, at("2024-01-03T02:30:00Z")));
+        assert!(!is_active(starts_at, 60, rrule, at("2024-01-02T02:30:00Z")));
+        assert!(!is_active(starts_at, 60, rrule, at("2024-01-08T02:30:00Z")));
+        assert!(is_active(starts_at, 60, rrule, at("2024-01-15T02:30:00Z")));
+    }
+
+    #[test]
+    fn monthly_window() {
+        let starts_at = at("2024-01-15T03:00:00Z");
+        let rrule = Some("FREQ=MONTHLY");
+        assert!(is_active(starts_at, 30, rrule, at("2024-02-15T03:10:00Z")));
+        assert!(!is_active(starts_at, 30, rrule, at("2024-02-16T03:10:00Z")));
+    }
+
+    #[test]
+    fn window_ends_at_until() {
+        let starts_at = at("2024-01-01T02:00:00Z");
+        let rrule = Some("FREQ=DAILY;UNTIL=20240103");
+        assert!(is_active(starts_at, 60, rrule, at("2024-01-03T02:30:00Z")));
+        assert!(!is_active(starts_at, 60, rrule, at("2024-01-04T02:30:00Z")));
+    }
+
+    #[test]
+    fn invalid_rule_is_a_one_off() {
+        let starts_at = at("2024-01-01T02:00:00Z");
+        let rrule = Some("FREQ=YEARLY");
+        assert!(is_active(starts_at, 60, rrule, at("2024-01-01T02:30:00Z")));
+        assert!(!is_active(starts_at, 60, rrule, at("2024-01-02T02:30:00Z")));
+    }
+}
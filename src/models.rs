@@ -31,7 +31,7 @@ pub struct User {
     pub name: String,
     pub created_at: DateTime<Utc>,
     pub registered_at: Option<DateTime<Utc>>,
-    pub discord_webhook: Option<String>,
+    pub expiry_thresholds: String,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -0,0 +1,155 @@
+use super::{Channel, Event, EventKind};
+use crate::incident;
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+
+pub const MAX_ATTEMPTS: i32 = 6;
+
+// 1, 2, 4, 8 and 16 minutes after the previous attempt
+fn backoff(attempt: i32) -> Duration {
+    Duration::minutes(2i64.pow(attempt as u32 - 1))
+}
+
+// sends the event and logs the attempt, failed attempts are scheduled for a retry
+pub async fn deliver(db: &Pool<Postgres>, channel: &Channel, event: &Event, attempt: i32) {
+    let mut event = event.clone();
+    let needs_attention = match event.kind {
+        EventKind::State => !event.is_up(),
+        EventKind::Escalation | EventKind::Reminder | EventKind::Flapping => true,
+        _ => false,
+    };
+    // retries rebuild the link, the stored one may point to an incident that is gone
+    event.ack_link = None;
+    if needs_attention {
+        if let Some(incident_id) = incident::pending(db, event.website_id).await {
+            event.ack_link = Some(incident::ack_link(incident_id, channel.id));
+        }
+    }
+    let event = &event;
+
+    let result = channel.send(event).await;
+
+    let (status, error) = match &result {
+        Ok(status) => (*status, None),
+        Err(err) => (err.status, Some(err.message.clone())),
+    };
+    let (state, next_attempt_at) = match (&result, attempt < MAX_ATTEMPTS) {
+        (Ok(_), _) => ("sent", None),
+        (Err(_), true) => ("retrying", Some(Utc::now() + backoff(attempt))),
+        (Err(_), false) => ("failed", None),
+    };
+
+    if let Some(error) = &error {
+        println!(
+            "error at {} channel {} attempt {}: {}",
+            channel.kind, channel.name, attempt, error
+        );
+    }
+
+    if matches!(
+        event.kind,
+        EventKind::State | EventKind::Escalation | EventKind::Reminder | EventKind::Flapping
+    ) {
+        let message = match &error {
+            None => format!("Sent \"{}\" to {}", event.title(), channel.name),
+            Some(error) => format!(
+                "Failed to send \"{}\" to {} (attempt {}): {}",
+                event.title(),
+                channel.name,
+                attempt,
+                error
+            ),
+        };
+        incident::record_latest(db, event.website_id, "notification", &message).await;
+    }
+
+    let payload = serde_json::to_value(event).unwrap();
+    let _ = sqlx::query!(
+        r#"insert into notification_delivery
+            (channel_id, website_id, payload, status, error, attempt, state, next_attempt_at)
+        values ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        channel.id,
+        event.website_id,
+        payload,
+        status.map(|s| s as i32),
+        error,
+        attempt,
+        state,
+        next_attempt_at
+    )
+    .execute(db)
+    .await;
+}
+
+// a state alert retried after the website changed state again would reopen the incident
+// on the receiving side (or post "down" after recovery), so those retries are dropped
+async fn is_stale(db: &Pool<Postgres>, event: &Event) -> bool {
+    if !matches!(
+        event.kind,
+        EventKind::State | EventKind::Escalation | EventKind::Reminder
+    ) {
+        return false;
+    }
+    sqlx::query!(
+        r#"select exists (select 1 from website_state
+            where website_id = $1 and created_at > $2) as "newer!""#,
+        event.website_id,
+        event.at
+    )
+    .fetch_one(db)
+    .await
+    .map(|r| r.newer)
+    .unwrap_or(false)
+}
+
+pub async fn retry_failed(db: Pool<Postgres>) {
+    loop {
+        // claim due retries, so a slow delivery isn't picked up twice
+        let due = sqlx::query!(
+            r#"update notification_delivery set state = 'retried'
+            where state = 'retrying' and next_attempt_at <= now()
+            returning id, channel_id, payload, attempt"#
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap_or_default();
+
+        for delivery in due {
+            let channel = sqlx::query_as!(
+                Channel,
+                r#"select c.id, c.kind, c.name, c.config, u.email as owner_email
+                from notification_channel c
+                join "user" u on u.id = c.user_id
+                where c.id = $1"#,
+                delivery.channel_id
+            )
+            .fetch_optional(&db)
+            .await
+            .unwrap_or(None);
+
+            let event = serde_json::from_value::<Event>(delivery.payload).ok();
+
+            if let Some(event) = &event {
+                if is_stale(&db, event).await {
+                    let _ = sqlx::query!(
+                        r#"update notification_delivery set state = 'dropped', next_attempt_at = null
+                        where id = $1"#,
+                        delivery.id
+                    )
+                    .execute(&db)
+                    .await;
+                    continue;
+                }
+            }
+
+            if let (Some(channel), Some(event)) = (channel, event) {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    deliver(&db, &channel, &event, delivery.attempt + 1).await;
+                });
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+    }
+}
@@ -0,0 +1,34 @@
+use super::{response, Event, Field, Kind, Notifier, SendResult};
+use crate::checker::HTTP;
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+pub const KIND: Kind = Kind {
+    id: "discord",
+    name: "Discord",
+    fields: &[Field {
+        name: "webhook",
+        label: "Webhook Url",
+        placeholder: "https://discord.com/api/webhooks/...",
+        secret: true,
+    }],
+};
+
+#[derive(Deserialize)]
+pub struct Discord {
+    pub webhook: String,
+}
+
+#[async_trait]
+impl Notifier for Discord {
+    async fn send(&self, event: &Event) -> SendResult {
+        let resp = HTTP
+            .post(&self.webhook)
+            .json(&json!({ "content": event.text() }))
+            .send()
+            .await?;
+
+        response("discord", &resp)
+    }
+}
@@ -0,0 +1,89 @@
+use super::{Event, EventKind, Field, Kind, Notifier, SendError, SendResult};
+use crate::app::mail::{self, AlertMail};
+use crate::expiry;
+use axum::async_trait;
+use serde_json::Value;
+
+pub const KIND: Kind = Kind {
+    id: "email",
+    name: "E-mail",
+    fields: &[Field {
+        name: "addresses",
+        label: "Extra addresses (the account owner is always included)",
+        placeholder: "ops@example.com, oncall@example.com",
+        secret: false,
+    }],
+};
+
+pub struct Email {
+    pub to: Vec<String>,
+}
+
+impl Email {
+    pub fn new(owner: &str, config: &Value) -> Option<Email> {
+        let mut to = vec![owner.to_string()];
+        let addresses = config.get("addresses").and_then(|a| a.as_str()).unwrap_or("");
+        for address in addresses.split([',', ';', ' ', '\n']).map(|a| a.trim()) {
+            if !address.is_empty() && !to.iter().any(|t| t == address) {
+                to.push(address.to_string());
+            }
+        }
+        Some(Email { to })
+    }
+}
+
+#[async_trait]
+impl Notifier for Email {
+    async fn send(&self, event: &Event) -> SendResult {
+        let mut message = match event.kind {
+            EventKind::State => format!(
+                "{} changed from {} to {} at {}.",
+                event.url,
+                event.old_state,
+                event.new_state,
+                event.at.format("%H:%M %d/%m/%Y UTC")
+            ),
+            EventKind::Audit => format!("The security audit of {} found changes.", event.url),
+            EventKind::Escalation => format!(
+                "{} is still {} and nobody has acknowledged it yet.",
+                event.url, event.new_state
+            ),
+            EventKind::Reminder => format!("{} has not recovered yet.", event.url),
+            EventKind::Expiry => format!(
+                "The {} of {} needs your attention.",
+                expiry::subject(event.expiry.as_deref().unwrap_or("")),
+                event.url
+            ),
+            EventKind::Flapping => format!("{} keeps changing state.", event.url),
+            EventKind::Test => "This is a test notification, your e-mail channel works.".to_string(),
+        };
+        for detail in event.details() {
+            message.push('\n');
+            message.push_str(&detail);
+        }
+
+        // alerts that can be acknowledged get the acknowledge button instead
+        let (link, button) = match &event.ack_link {
+            Some(ack_link) => {
+                message.push_str(&format!("\nOpen in AllGreen: {}", event.link()));
+                (ack_link.clone(), "Acknowledge".to_string())
+            }
+            None => (event.link(), "Open Website".to_string()),
+        };
+
+        mail::send_alert_mail(
+            self.to.clone(),
+            AlertMail {
+                subject: format!("[AllGreen] {}", event.title()),
+                title: event.title(),
+                message,
+                link,
+                button,
+                color: event.color().to_string(),
+            },
+        )
+        .await
+        .map(|_| None)
+        .map_err(SendError::from)
+    }
+}
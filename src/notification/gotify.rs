@@ -0,0 +1,59 @@
+use super::{response, Event, Field, Kind, Notifier, SendResult};
+use crate::checker::HTTP;
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+pub const KIND: Kind = Kind {
+    id: "gotify",
+    name: "Gotify",
+    fields: &[
+        Field {
+            name: "server",
+            label: "Server Url",
+            placeholder: "https://gotify.example.com",
+            secret: false,
+        },
+        Field {
+            name: "token",
+            label: "Application Token",
+            placeholder: "AbCdEf...",
+            secret: true,
+        },
+    ],
+};
+
+#[derive(Deserialize)]
+pub struct Gotify {
+    pub server: String,
+    pub token: String,
+}
+
+#[async_trait]
+impl Notifier for Gotify {
+    async fn send(&self, event: &Event) -> SendResult {
+        let priority = match event.level() {
+            "up" => 5,
+            "down" => 10,
+            _ => 8,
+        };
+        let mut message = event.details();
+        message.push(event.link());
+
+        let resp = HTTP
+            .post(format!("{}/message", self.server.trim_end_matches('/')))
+            .header("X-Gotify-Key", &self.token)
+            .json(&json!({
+                "title": event.title(),
+                "message": message.join("\n"),
+                "priority": priority,
+                "extras": {
+                    "client::notification": { "click": { "url": event.link() } }
+                }
+            }))
+            .send()
+            .await?;
+
+        response("gotify", &resp)
+    }
+}
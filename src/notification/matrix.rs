@@ -0,0 +1,84 @@
+use super::{response, Event, Field, Kind, Notifier, SendError, SendResult};
+use crate::checker::HTTP;
+use axum::async_trait;
+use maud::html;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+pub const KIND: Kind = Kind {
+    id: "matrix",
+    name: "Matrix",
+    fields: &[
+        Field {
+            name: "homeserver",
+            label: "Homeserver Url",
+            placeholder: "https://matrix.org",
+            secret: false,
+        },
+        Field {
+            name: "access_token",
+            label: "Access Token",
+            placeholder: "syt_...",
+            secret: true,
+        },
+        Field {
+            name: "room_id",
+            label: "Room Id",
+            placeholder: "!abcdef:matrix.org",
+            secret: false,
+        },
+    ],
+};
+
+#[derive(Deserialize)]
+pub struct Matrix {
+    pub homeserver: String,
+    pub access_token: String,
+    pub room_id: String,
+}
+
+#[async_trait]
+impl Notifier for Matrix {
+    async fn send(&self, event: &Event) -> SendResult {
+        let mut url = Url::parse(&self.homeserver).map_err(|e| SendError::from(e.to_string()))?;
+        let txn_id = Uuid::new_v4().to_string();
+        url.path_segments_mut()
+            .map_err(|_| SendError::from("invalid homeserver url".to_string()))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                self.room_id.as_str(),
+                "send",
+                "m.room.message",
+                txn_id.as_str(),
+            ]);
+
+        let formatted = html! {
+            b { (event.title()) }
+            @for detail in event.details() {
+                br; (detail)
+            }
+            br; a href=(event.link()) { "Open in AllGreen" }
+        }
+        .into_string();
+
+        let resp = HTTP
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(&json!({
+                "msgtype": "m.text",
+                "body": format!("{}\n{}", event.text(), event.link()),
+                "format": "org.matrix.custom.html",
+                "formatted_body": formatted
+            }))
+            .send()
+            .await?;
+
+        response("matrix", &resp)
+    }
+}
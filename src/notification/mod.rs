@@ -0,0 +1,343 @@
+use crate::appconfig::ENV;
+use crate::{crypto, expiry};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+pub mod delivery;
+pub mod discord;
+pub mod email;
+pub mod gotify;
+pub mod matrix;
+pub mod ntfy;
+pub mod opsgenie;
+pub mod pagerduty;
+pub mod slack;
+pub mod teams;
+pub mod telegram;
+pub mod webhook;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    State,
+    Audit,
+    Escalation,
+    Reminder,
+    Expiry,
+    Flapping,
+    Test,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub kind: EventKind,
+    pub website_id: Uuid,
+    pub url: String,
+    pub old_state: String,
+    pub new_state: String,
+    pub reason: Option<String>,
+    pub duration: i64,
+    pub downtime: Option<i64>,
+    pub at: DateTime<Utc>,
+    // signed acknowledge link, filled in per channel while an incident needs attention
+    #[serde(default)]
+    pub ack_link: Option<String>,
+    // "domain" or "ssl" for expiry alerts
+    #[serde(default)]
+    pub expiry: Option<String>,
+}
+
+// "2h 15m" style duration from seconds
+pub fn format_duration(seconds: i64) -> String {
+    let days = seconds / 86400;
+    let hours = seconds % 86400 / 3600;
+    let minutes = seconds % 3600 / 60;
+
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", seconds.max(0))
+    }
+}
+
+impl Event {
+    pub fn test() -> Event {
+        Event {
+            kind: EventKind::Test,
+            website_id: Uuid::nil(),
+            url: ENV.host.clone(),
+            old_state: "up".to_string(),
+            new_state: "up".to_string(),
+            reason: None,
+            duration: 0,
+            downtime: None,
+            at: Utc::now(),
+            ack_link: None,
+            expiry: None,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self.kind {
+            EventKind::State => format!("{} is {}", self.url, self.new_state),
+            EventKind::Audit => format!("{} security audit", self.url),
+            EventKind::Escalation => format!("{} is still {}", self.url, self.new_state),
+            EventKind::Reminder => format!(
+                "{} still {} for {}",
+                self.url,
+                self.new_state,
+                format_duration(self.downtime.unwrap_or(0))
+            ),
+            EventKind::Expiry => {
+                format!("{} {} expiry", self.url, expiry::subject(self.expiry.as_deref().unwrap_or("")))
+            }
+            EventKind::Flapping => format!("{} is flapping", self.url),
+            EventKind::Test => "Test notification from AllGreen".to_string(),
+        }
+    }
+
+    // reason and downtime, one per line
+    pub fn details(&self) -> Vec<String> {
+        let mut details = vec![];
+        if let Some(reason) = &self.reason {
+            details.push(format!("Reason: {}", reason));
+        }
+        if let Some(downtime) = self.downtime {
+            details.push(format!("Downtime: {}", format_duration(downtime)));
+        }
+        details
+    }
+
+    // title and details in one line, for channels without formatting
+    pub fn text(&self) -> String {
+        let details = self.details();
+        if details.is_empty() {
+            self.title()
+        } else {
+            format!("{} ({})", self.title(), details.join(", "))
+        }
+    }
+
+    pub fn link(&self) -> String {
+        format!("{}/website/{}", ENV.host, self.website_id)
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.new_state == "up"
+    }
+
+    // "up", "down" or "warning" for picking icons and priorities, alerts that aren't
+    // state changes are always warnings
+    pub fn level(&self) -> &'static str {
+        match (self.kind, self.new_state.as_str()) {
+            (EventKind::Audit | EventKind::Expiry, _) => "warning",
+            (_, "up") => "up",
+            (_, "down") => "down",
+            _ => "warning",
+        }
+    }
+
+    pub fn color(&self) -> &'static str {
+        match (self.kind, self.new_state.as_str()) {
+            (EventKind::Test, _) | (EventKind::State, "up") => "#2dc26b",
+            (EventKind::State | EventKind::Escalation | EventKind::Reminder, "down") => "#f14668",
+            _ => "#ffb70f",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SendError {
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+impl From<reqwest::Error> for SendError {
+    fn from(err: reqwest::Error) -> Self {
+        SendError {
+            status: err.status().map(|s| s.as_u16()),
+            // telegram and discord carry their token in the url, keep it out of the delivery log
+            message: err.without_url().to_string(),
+        }
+    }
+}
+
+impl From<String> for SendError {
+    fn from(message: String) -> Self {
+        SendError {
+            status: None,
+            message,
+        }
+    }
+}
+
+// the HTTP status of the delivery when the channel talks HTTP
+pub type SendResult = Result<Option<u16>, SendError>;
+
+pub fn response(service: &str, resp: &reqwest::Response) -> SendResult {
+    let status = resp.status();
+    if status.is_success() {
+        Ok(Some(status.as_u16()))
+    } else {
+        Err(SendError {
+            status: Some(status.as_u16()),
+            message: format!("{} responded {}", service, status),
+        })
+    }
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, event: &Event) -> SendResult;
+}
+
+pub struct Field {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub placeholder: &'static str,
+    // stored encrypted with the app secret
+    pub secret: bool,
+}
+
+pub struct Kind {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub fields: &'static [Field],
+}
+
+pub const KINDS: &[Kind] = &[
+    discord::KIND,
+    email::KIND,
+    slack::KIND,
+    telegram::KIND,
+    webhook::KIND,
+    pagerduty::KIND,
+    opsgenie::KIND,
+    teams::KIND,
+    matrix::KIND,
+    ntfy::KIND,
+    gotify::KIND,
+];
+
+pub fn kind(id: &str) -> Option<&'static Kind> {
+    KINDS.iter().find(|k| k.id == id)
+}
+
+// channels saved before encryption keep working with their plain values
+fn decrypted(channel: &Channel) -> Value {
+    let mut config = channel.config.clone();
+    let fields = kind(&channel.kind).map(|k| k.fields).unwrap_or_default();
+    if let Value::Object(values) = &mut config {
+        for field in fields.iter().filter(|f| f.secret) {
+            if let Some(Value::String(value)) = values.get_mut(field.name) {
+                if let Some(plain) = crypto::decrypt(value) {
+                    *value = plain;
+                }
+            }
+        }
+    }
+    config
+}
+
+pub fn notifier(channel: &Channel) -> Option<Box<dyn Notifier>> {
+    fn boxed<T: Notifier + serde::de::DeserializeOwned + 'static>(
+        config: &Value,
+    ) -> Option<Box<dyn Notifier>> {
+        serde_json::from_value::<T>(config.clone())
+            .ok()
+            .map(|n| Box::new(n) as Box<dyn Notifier>)
+    }
+
+    let config = decrypted(channel);
+    match channel.kind.as_str() {
+        "discord" => boxed::<discord::Discord>(&config),
+        "slack" => boxed::<slack::Slack>(&config),
+        "telegram" => boxed::<telegram::Telegram>(&config),
+        "webhook" => boxed::<webhook::Webhook>(&config),
+        "pagerduty" => boxed::<pagerduty::PagerDuty>(&config),
+        "opsgenie" => boxed::<opsgenie::Opsgenie>(&config),
+        "teams" => boxed::<teams::Teams>(&config),
+        "matrix" => boxed::<matrix::Matrix>(&config),
+        "ntfy" => boxed::<ntfy::Ntfy>(&config),
+        "gotify" => boxed::<gotify::Gotify>(&config),
+        "email" => email::Email::new(&channel.owner_email, &config)
+            .map(|n| Box::new(n) as Box<dyn Notifier>),
+        _ => None,
+    }
+}
+
+pub struct Channel {
+    pub id: Uuid,
+    pub kind: String,
+    pub name: String,
+    pub config: Value,
+    pub owner_email: String,
+}
+
+impl Channel {
+    pub async fn send(&self, event: &Event) -> SendResult {
+        match notifier(self) {
+            Some(notifier) => notifier.send(event).await,
+            None => Err(SendError::from(format!(
+                "invalid {} channel configuration",
+                self.kind
+            ))),
+        }
+    }
+}
+
+// channels selected for the website, or every channel of the account when none is selected
+// state changes and reminders of a monitor with an escalation policy only reach the steps
+// escalated so far
+pub async fn channels_for(db: &Pool<Postgres>, event: &Event) -> Vec<Channel> {
+    sqlx::query_as!(
+        Channel,
+        r#"select c.id, c.kind, c.name, c.config, u.email as owner_email
+        from notification_channel c
+        join website ws on ws.user_id = c.user_id
+        join "user" u on u.id = c.user_id
+        where ws.id = $1 and case
+            when ws.escalation_policy_id is null or not $2 then (
+                not exists (select 1 from website_channel wc where wc.website_id = ws.id)
+                or exists (select 1 from website_channel wc
+                    where wc.website_id = ws.id and wc.channel_id = c.id)
+            )
+            else c.id in (
+                select es.channel_id from (
+                    select channel_id,
+                        row_number() over (order by delay_minutes, id) as n
+                    from escalation_step where policy_id = ws.escalation_policy_id
+                ) es
+                join lateral (
+                    select escalation_step from incident
+                    where website_id = ws.id
+                    order by opened_at desc limit 1
+                ) i on true
+                where es.n <= i.escalation_step
+            )
+        end"#,
+        event.website_id,
+        matches!(event.kind, EventKind::State | EventKind::Reminder)
+    )
+    .fetch_all(db)
+    .await
+    .unwrap_or_default()
+}
+
+pub async fn notify(db: &Pool<Postgres>, event: Event) {
+    for channel in channels_for(db, &event).await {
+        let db = db.clone();
+        let event = event.clone();
+        tokio::spawn(async move {
+            delivery::deliver(&db, &channel, &event, 1).await;
+        });
+    }
+}
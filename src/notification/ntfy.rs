@@ -0,0 +1,71 @@
+use super::{response, Event, Field, Kind, Notifier, SendResult};
+use crate::checker::HTTP;
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+pub const KIND: Kind = Kind {
+    id: "ntfy",
+    name: "ntfy",
+    fields: &[
+        Field {
+            name: "server",
+            label: "Server Url",
+            placeholder: "https://ntfy.sh",
+            secret: false,
+        },
+        Field {
+            name: "topic",
+            label: "Topic",
+            placeholder: "allgreen-alerts",
+            secret: false,
+        },
+        Field {
+            name: "token",
+            label: "Access Token (optional)",
+            placeholder: "tk_...",
+            secret: true,
+        },
+    ],
+};
+
+#[derive(Deserialize)]
+pub struct Ntfy {
+    pub server: String,
+    pub topic: String,
+    #[serde(default)]
+    pub token: String,
+}
+
+#[async_trait]
+impl Notifier for Ntfy {
+    async fn send(&self, event: &Event) -> SendResult {
+        let server = if self.server.is_empty() {
+            "https://ntfy.sh"
+        } else {
+            self.server.trim_end_matches('/')
+        };
+        let (priority, tags) = match event.level() {
+            "up" => (3, "white_check_mark"),
+            "down" => (5, "rotating_light"),
+            _ => (4, "warning"),
+        };
+
+        // the JSON publish body, titles with non-ASCII names aren't valid header values
+        let mut req = HTTP.post(server).json(&json!({
+            "topic": self.topic,
+            "title": event.title(),
+            "message": event.details().join("\n"),
+            "priority": priority,
+            "tags": [tags],
+            "click": event.link(),
+        }));
+        if !self.token.is_empty() {
+            req = req.bearer_auth(&self.token);
+        }
+
+        let resp = req.send().await?;
+
+        response("ntfy", &resp)
+    }
+}
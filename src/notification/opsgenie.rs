@@ -0,0 +1,93 @@
+use super::{
+    pagerduty::dedup_key, response, Event, EventKind, Field, Kind, Notifier, SendResult,
+};
+use crate::appconfig::ENV;
+use crate::checker::HTTP;
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+pub const KIND: Kind = Kind {
+    id: "opsgenie",
+    name: "Opsgenie",
+    fields: &[Field {
+        name: "api_key",
+        label: "API Integration Key",
+        placeholder: "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx",
+        secret: true,
+    }],
+};
+
+#[derive(Deserialize)]
+pub struct Opsgenie {
+    pub api_key: String,
+}
+
+fn api_url() -> String {
+    ENV.opsgenie_api_url
+        .clone()
+        .unwrap_or("https://api.opsgenie.com".to_string())
+}
+
+impl Opsgenie {
+    async fn post(&self, path: String, body: serde_json::Value) -> SendResult {
+        let resp = HTTP
+            .post(format!("{}{}", api_url(), path))
+            .header("Authorization", format!("GenieKey {}", self.api_key))
+            .json(&body)
+            .send()
+            .await?;
+
+        response("opsgenie", &resp)
+    }
+
+    async fn create(&self, event: &Event) -> SendResult {
+        let priority = match event.level() {
+            "down" => "P1",
+            _ => "P3",
+        };
+
+        self.post(
+            "/v2/alerts".to_string(),
+            json!({
+                "message": event.title(),
+                "alias": dedup_key(event),
+                "description": format!("{}\n{}", event.details().join("\n"), event.link()),
+                "source": "AllGreen",
+                "priority": priority,
+                "details": {
+                    "url": event.url,
+                    "old_state": event.old_state,
+                    "new_state": event.new_state,
+                    "latency_ms": event.duration.to_string(),
+                }
+            }),
+        )
+        .await
+    }
+
+    async fn close(&self, event: &Event) -> SendResult {
+        self.post(
+            format!("/v2/alerts/{}/close?identifierType=alias", dedup_key(event)),
+            json!({
+                "source": "AllGreen",
+                "note": event.text(),
+            }),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl Notifier for Opsgenie {
+    async fn send(&self, event: &Event) -> SendResult {
+        match event.kind {
+            EventKind::State if event.is_up() => self.close(event).await,
+            EventKind::Test => {
+                self.create(event).await?;
+                self.close(event).await
+            }
+            _ => self.create(event).await,
+        }
+    }
+}
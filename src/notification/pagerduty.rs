@@ -0,0 +1,89 @@
+use super::{response, Event, EventKind, Field, Kind, Notifier, SendResult};
+use crate::appconfig::ENV;
+use crate::checker::HTTP;
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+pub const KIND: Kind = Kind {
+    id: "pagerduty",
+    name: "PagerDuty",
+    fields: &[Field {
+        name: "routing_key",
+        label: "Events v2 Integration Key",
+        placeholder: "R0ABCDEF1234567890",
+        secret: true,
+    }],
+};
+
+#[derive(Deserialize)]
+pub struct PagerDuty {
+    pub routing_key: String,
+}
+
+fn api_url() -> String {
+    ENV.pagerduty_api_url
+        .clone()
+        .unwrap_or("https://events.pagerduty.com".to_string())
+}
+
+// one dedup key per monitor, so the resolve event closes the incident opened by the trigger
+pub fn dedup_key(event: &Event) -> String {
+    match event.kind {
+        EventKind::Audit => format!("allgreen-audit-{}", event.website_id),
+        EventKind::Expiry => format!(
+            "allgreen-{}-{}",
+            event.expiry.as_deref().unwrap_or("expiry"),
+            event.website_id
+        ),
+        _ => format!("allgreen-{}", event.website_id),
+    }
+}
+
+impl PagerDuty {
+    async fn enqueue(&self, event: &Event, action: &str) -> SendResult {
+        let severity = match event.level() {
+            "down" => "critical",
+            _ => "warning",
+        };
+
+        let resp = HTTP
+            .post(format!("{}/v2/enqueue", api_url()))
+            .json(&json!({
+                "routing_key": self.routing_key,
+                "event_action": action,
+                "dedup_key": dedup_key(event),
+                "payload": {
+                    "summary": event.text(),
+                    "source": event.url,
+                    "severity": severity,
+                    "timestamp": event.at.to_rfc3339(),
+                    "custom_details": {
+                        "old_state": event.old_state,
+                        "new_state": event.new_state,
+                        "reason": event.reason,
+                        "latency_ms": event.duration,
+                    }
+                },
+                "links": [{ "href": event.link(), "text": "Open in AllGreen" }]
+            }))
+            .send()
+            .await?;
+
+        response("pagerduty", &resp)
+    }
+}
+
+#[async_trait]
+impl Notifier for PagerDuty {
+    async fn send(&self, event: &Event) -> SendResult {
+        match event.kind {
+            EventKind::State if event.is_up() => self.enqueue(event, "resolve").await,
+            EventKind::Test => {
+                self.enqueue(event, "trigger").await?;
+                self.enqueue(event, "resolve").await
+            }
+            _ => self.enqueue(event, "trigger").await,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use super::{format_duration, response, Event, Field, Kind, Notifier, SendResult};
+use crate::checker::HTTP;
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+pub const KIND: Kind = Kind {
+    id: "slack",
+    name: "Slack",
+    fields: &[Field {
+        name: "webhook",
+        label: "Incoming Webhook Url",
+        placeholder: "https://hooks.slack.com/services/...",
+        secret: true,
+    }],
+};
+
+#[derive(Deserialize)]
+pub struct Slack {
+    pub webhook: String,
+}
+
+// Block Kit message inside a coloured attachment
+pub fn message(event: &Event) -> serde_json::Value {
+    let state = if event.old_state.is_empty() || event.old_state == event.new_state {
+        event.new_state.clone()
+    } else {
+        format!("{} → {}", event.old_state, event.new_state)
+    };
+    let mut fields = vec![
+        json!({ "type": "mrkdwn", "text": format!("*Url*\n<{}>", event.url) }),
+        json!({ "type": "mrkdwn", "text": format!("*State*\n{}", state) }),
+    ];
+    if let Some(reason) = &event.reason {
+        fields.push(json!({ "type": "mrkdwn", "text": format!("*Reason*\n{}", reason) }));
+    }
+    if event.duration > 0 {
+        fields.push(json!({ "type": "mrkdwn", "text": format!("*Response Time*\n{} ms", event.duration) }));
+    }
+    if let Some(downtime) = event.downtime {
+        fields.push(json!({ "type": "mrkdwn", "text": format!("*Total Downtime*\n{}", format_duration(downtime)) }));
+    }
+
+    let mut buttons = vec![json!({
+        "type": "button",
+        "text": { "type": "plain_text", "text": "Open in AllGreen" },
+        "url": event.link()
+    })];
+    if let Some(ack_link) = &event.ack_link {
+        buttons.push(json!({
+            "type": "button",
+            "style": "danger",
+            "text": { "type": "plain_text", "text": "Acknowledge" },
+            "url": ack_link
+        }));
+    }
+
+    json!({
+        "text": event.text(),
+        "attachments": [{
+            "color": event.color(),
+            "blocks": [
+                {
+                    "type": "header",
+                    "text": { "type": "plain_text", "text": event.title() }
+                },
+                { "type": "section", "fields": fields },
+                { "type": "actions", "elements": buttons },
+                {
+                    "type": "context",
+                    "elements": [{
+                        "type": "mrkdwn",
+                        "text": event.at.format("%H:%M:%S %d/%m/%Y UTC").to_string()
+                    }]
+                }
+            ]
+        }]
+    })
+}
+
+#[async_trait]
+impl Notifier for Slack {
+    async fn send(&self, event: &Event) -> SendResult {
+        let resp = HTTP
+            .post(&self.webhook)
+            .json(&message(event))
+            .send()
+            .await?;
+
+        response("slack", &resp)
+    }
+}
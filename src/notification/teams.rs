@@ -0,0 +1,76 @@
+use super::{response, Event, Field, Kind, Notifier, SendResult};
+use crate::checker::HTTP;
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+pub const KIND: Kind = Kind {
+    id: "teams",
+    name: "Microsoft Teams",
+    fields: &[Field {
+        name: "webhook",
+        label: "Webhook Url",
+        placeholder: "https://example.webhook.office.com/...",
+        secret: true,
+    }],
+};
+
+#[derive(Deserialize)]
+pub struct Teams {
+    pub webhook: String,
+}
+
+pub fn card(event: &Event) -> serde_json::Value {
+    let color = match event.level() {
+        "up" => "Good",
+        "down" => "Attention",
+        _ => "Warning",
+    };
+    let mut facts = vec![json!({ "title": "Url", "value": event.url })];
+    for detail in event.details() {
+        if let Some((title, value)) = detail.split_once(": ") {
+            facts.push(json!({ "title": title, "value": value }));
+        }
+    }
+
+    json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "content": {
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "type": "AdaptiveCard",
+                "version": "1.4",
+                "body": [
+                    {
+                        "type": "TextBlock",
+                        "size": "Medium",
+                        "weight": "Bolder",
+                        "color": color,
+                        "text": event.title(),
+                        "wrap": true
+                    },
+                    { "type": "FactSet", "facts": facts }
+                ],
+                "actions": [{
+                    "type": "Action.OpenUrl",
+                    "title": "Open in AllGreen",
+                    "url": event.link()
+                }]
+            }
+        }]
+    })
+}
+
+#[async_trait]
+impl Notifier for Teams {
+    async fn send(&self, event: &Event) -> SendResult {
+        let resp = HTTP
+            .post(&self.webhook)
+            .json(&card(event))
+            .send()
+            .await?;
+
+        response("teams", &resp)
+    }
+}
@@ -0,0 +1,91 @@
+use super::{Event, Field, Kind, Notifier, SendError, SendResult};
+use crate::appconfig::ENV;
+use crate::checker::HTTP;
+use axum::async_trait;
+use maud::html;
+use serde::Deserialize;
+use serde_json::json;
+
+pub const KIND: Kind = Kind {
+    id: "telegram",
+    name: "Telegram",
+    fields: &[
+        Field {
+            name: "bot_token",
+            label: "Bot Token",
+            placeholder: "123456:ABC-DEF...",
+            secret: true,
+        },
+        Field {
+            name: "chat_id",
+            label: "Chat Id",
+            placeholder: "-1001234567890",
+            secret: false,
+        },
+    ],
+};
+
+#[derive(Deserialize)]
+pub struct Telegram {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+#[derive(Deserialize)]
+struct TelegramResponse {
+    ok: bool,
+    description: Option<String>,
+}
+
+// TELEGRAM_API_URL points the channel to a stand-in server in tests
+fn api_url() -> String {
+    ENV.telegram_api_url
+        .clone()
+        .unwrap_or("https://api.telegram.org".to_string())
+}
+
+pub fn message(event: &Event) -> String {
+    let icon = match event.level() {
+        "up" => "🟢",
+        "down" => "🔴",
+        _ => "🟡",
+    };
+
+    html! {
+        (icon) " " b { (event.title()) } "\n"
+        @for detail in event.details() {
+            (detail) "\n"
+        }
+        a href=(event.link()) { "Open in AllGreen" }
+    }
+    .into_string()
+}
+
+#[async_trait]
+impl Notifier for Telegram {
+    async fn send(&self, event: &Event) -> SendResult {
+        let resp = HTTP
+            .post(format!("{}/bot{}/sendMessage", api_url(), self.bot_token))
+            .json(&json!({
+                "chat_id": self.chat_id,
+                "text": message(event),
+                "parse_mode": "HTML",
+                "disable_web_page_preview": true
+            }))
+            .send()
+            .await?;
+
+        let status = Some(resp.status().as_u16());
+        let body: Option<TelegramResponse> = resp.json().await.ok();
+
+        match body {
+            Some(body) if body.ok => Ok(status),
+            body => Err(SendError {
+                status,
+                message: body
+                    .and_then(|b| b.description)
+                    .unwrap_or(format!("telegram responded {}", status.unwrap_or(0))),
+            }),
+        }
+    }
+}
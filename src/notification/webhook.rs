@@ -0,0 +1,95 @@
+use super::{response, Event, EventKind, Field, Kind, Notifier, SendResult};
+use crate::checker::HTTP;
+use crate::crypto;
+use axum::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+pub const KIND: Kind = Kind {
+    id: "webhook",
+    name: "Webhook",
+    fields: &[
+        Field {
+            name: "url",
+            label: "Url",
+            placeholder: "https://example.com/allgreen",
+            secret: false,
+        },
+        Field {
+            name: "secret",
+            label: "Signing Secret",
+            placeholder: "used for the X-AllGreen-Signature header",
+            secret: true,
+        },
+    ],
+};
+
+pub const VERSION: i32 = 1;
+
+#[derive(Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    pub secret: String,
+}
+
+pub fn payload(event: &Event) -> serde_json::Value {
+    let kind = match event.kind {
+        EventKind::State => "state_changed",
+        EventKind::Audit => "security_audit",
+        EventKind::Escalation => "escalation",
+        EventKind::Reminder => "reminder",
+        EventKind::Expiry => "expiry",
+        EventKind::Flapping => "flapping",
+        EventKind::Test => "test",
+    };
+
+    json!({
+        "version": VERSION,
+        "type": kind,
+        "monitor": {
+            "id": event.website_id,
+            "url": event.url,
+            "link": event.link(),
+        },
+        "old_state": event.old_state,
+        "new_state": event.new_state,
+        "reason": event.reason,
+        "latency_ms": event.duration,
+        "downtime_seconds": event.downtime,
+        "acknowledge_url": event.ack_link,
+        "expiry": event.expiry,
+        "occurred_at": event.at.to_rfc3339(),
+        "sent_at": Utc::now().to_rfc3339(),
+    })
+}
+
+impl Webhook {
+    // signature covers "<timestamp>.<body>" so a captured request can't be replayed later
+    async fn post(&self, body: &str) -> SendResult {
+        let timestamp = Utc::now().timestamp().to_string();
+        let signature = crypto::sign(&self.secret, &format!("{}.{}", timestamp, body));
+
+        let resp = HTTP
+            .post(&self.url)
+            .header("content-type", "application/json")
+            .header("X-AllGreen-Event-Version", VERSION.to_string())
+            .header("X-AllGreen-Timestamp", &timestamp)
+            .header("X-AllGreen-Signature", format!("sha256={}", signature))
+            .timeout(Duration::from_secs(10))
+            .body(body.to_string())
+            .send()
+            .await?;
+
+        response("webhook", &resp)
+    }
+}
+
+#[async_trait]
+impl Notifier for Webhook {
+    // failed deliveries are retried with backoff by the delivery worker
+    async fn send(&self, event: &Event) -> SendResult {
+        self.post(&payload(event).to_string()).await
+    }
+}
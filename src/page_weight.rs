@@ -0,0 +1,126 @@
+use crate::checker::{self, CheckOptions};
+use futures::{stream, StreamExt};
+use reqwest::header::CONTENT_LENGTH;
+use reqwest::{Method, Url};
+use scraper::{Html, Selector};
+
+const MAX_ASSET_REQUESTS: usize = 100;
+
+#[derive(Clone, Default)]
+pub struct WeightLimits {
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    pub max_assets: Option<i32>,
+    pub max_assets_size: Option<i64>,
+}
+
+impl WeightLimits {
+    pub fn is_empty(&self) -> bool {
+        self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.max_assets.is_none()
+            && self.max_assets_size.is_none()
+    }
+}
+
+// scripts, stylesheets and images referenced by the page
+fn assets(base: &Url, html: &str) -> Vec<Url> {
+    let document = Html::parse_document(html);
+    let selectors = [
+        ("script[src]", "src"),
+        ("link[rel=stylesheet][href]", "href"),
+        ("img[src]", "src"),
+    ];
+
+    let mut assets: Vec<Url> = vec![];
+    for (selector, attr) in selectors {
+        let selector = Selector::parse(selector).unwrap();
+        for element in document.select(&selector) {
+            let url = element.value().attr(attr).and_then(|src| base.join(src).ok());
+            if let Some(url) = url {
+                if !assets.contains(&url) {
+                    assets.push(url);
+                }
+            }
+        }
+    }
+
+    assets
+}
+
+async fn asset_size(http: &reqwest::Client, opts: &CheckOptions, useragent: &str, url: Url) -> i64 {
+    let resp = checker::send(http, opts, Method::HEAD, url.as_str(), useragent).await;
+
+    let length = resp.ok().and_then(|resp| {
+        resp.headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.parse::<i64>().ok())
+    });
+
+    match length {
+        Some(length) => length,
+        // servers answering HEAD without a length, download the asset instead
+        None => match checker::send(http, opts, Method::GET, url.as_str(), useragent).await {
+            Ok(resp) => resp.bytes().await.map(|b| b.len() as i64).unwrap_or(0),
+            Err(_) => 0,
+        },
+    }
+}
+
+// returns the reason of the first failed assertion
+pub async fn check(
+    http: &reqwest::Client,
+    opts: &CheckOptions,
+    useragent: &str,
+    url: &Url,
+    body: &str,
+) -> Option<String> {
+    let limits = &opts.weight;
+    let size = body.len() as i64;
+
+    if let Some(min_size) = limits.min_size {
+        if size < min_size {
+            return Some(format!("page is {} bytes, below minimum {}", size, min_size));
+        }
+    }
+    if let Some(max_size) = limits.max_size {
+        if size > max_size {
+            return Some(format!("page is {} bytes, above maximum {}", size, max_size));
+        }
+    }
+
+    if limits.max_assets.is_none() && limits.max_assets_size.is_none() {
+        return None;
+    }
+
+    let assets = assets(url, body);
+
+    // a negative limit from an older row means no limit
+    if let Some(max_assets) = limits.max_assets.and_then(|m| usize::try_from(m).ok()) {
+        if assets.len() > max_assets {
+            return Some(format!(
+                "page references {} assets, above maximum {}",
+                assets.len(),
+                max_assets
+            ));
+        }
+    }
+
+    if let Some(max_assets_size) = limits.max_assets_size {
+        let total: i64 = stream::iter(assets.into_iter().take(MAX_ASSET_REQUESTS))
+            .map(|asset| asset_size(http, opts, useragent, asset))
+            .buffer_unordered(10)
+            .fold(0, |total, size| async move { total + size })
+            .await;
+
+        if total > max_assets_size {
+            return Some(format!(
+                "assets are {} bytes, above maximum {}",
+                total, max_assets_size
+            ));
+        }
+    }
+
+    None
+}
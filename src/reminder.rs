@@ -0,0 +1,94 @@
+use crate::notification::{self, delivery, Event, EventKind};
+use chrono::{Duration as ChronoDuration, Utc};
+use sqlx::{Pool, Postgres};
+use std::time::Duration;
+
+// re-sends "still down" to each channel of an unacknowledged incident, the channel
+// interval and cap take precedence over the monitor ones
+pub async fn remind(db: Pool<Postgres>) {
+    loop {
+        let incidents = sqlx::query!(
+            r#"select i.id, i.website_id, i.opened_at, ws.url,
+                last.state as "state!", last.reason
+            from incident i
+            join website ws on ws.id = i.website_id
+            join lateral (
+                select state, reason from website_state
+                where website_id = ws.id
+                order by created_at desc limit 1
+            ) as last on true
+            where i.resolved_at is null and i.acknowledged_at is null and not ws.is_flapping
+                and last.state not in ('maintenance', 'dependency_down')"#
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap_or_default();
+
+        for incident in incidents {
+            let event = Event {
+                kind: EventKind::Reminder,
+                website_id: incident.website_id,
+                url: incident.url.clone(),
+                old_state: incident.state.clone(),
+                new_state: incident.state.clone(),
+                reason: incident.reason.clone(),
+                duration: 0,
+                downtime: Some((Utc::now() - incident.opened_at).num_seconds()),
+                at: Utc::now(),
+                ack_link: None,
+                expiry: None,
+            };
+
+            for channel in notification::channels_for(&db, &event).await {
+                let reminder = sqlx::query!(
+                    r#"select coalesce(c.reminder_interval, ws.reminder_interval) as interval,
+                        coalesce(c.reminder_limit, ws.reminder_limit) as limit,
+                        r.sent as "sent?", r.last_sent_at as "last_sent_at?"
+                    from notification_channel c
+                    join website ws on ws.id = $2
+                    left join incident_reminder r on r.incident_id = $3 and r.channel_id = c.id
+                    where c.id = $1"#,
+                    channel.id,
+                    incident.website_id,
+                    incident.id
+                )
+                .fetch_one(&db)
+                .await;
+
+                let Ok(reminder) = reminder else {
+                    continue;
+                };
+                let Some(interval) = reminder.interval.filter(|i| *i > 0) else {
+                    continue;
+                };
+                let sent = reminder.sent.unwrap_or(0);
+                if reminder.limit.map_or(false, |limit| sent >= limit) {
+                    continue;
+                }
+                let since = reminder.last_sent_at.unwrap_or(incident.opened_at);
+                if Utc::now() < since + ChronoDuration::minutes(interval as i64) {
+                    continue;
+                }
+
+                let _ = sqlx::query!(
+                    r#"insert into incident_reminder (incident_id, channel_id, sent, last_sent_at)
+                    values ($1, $2, 1, now())
+                    on conflict (incident_id, channel_id)
+                    do update set sent = incident_reminder.sent + 1, last_sent_at = now()"#,
+                    incident.id,
+                    channel.id
+                )
+                .execute(&db)
+                .await;
+
+                let db = db.clone();
+                let event = event.clone();
+                tokio::spawn(async move {
+                    delivery::deliver(&db, &channel, &event, 1).await;
+                });
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+}
@@ -0,0 +1,192 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// a state lasts from its check until the next state change
+pub struct Span {
+    pub state: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+// spans since `from` per website, including the state the website was in at `from`
+pub async fn spans(
+    db: &Pool<Postgres>,
+    ids: &[Uuid],
+    from: DateTime<Utc>,
+) -> HashMap<Uuid, Vec<Span>> {
+    let states = sqlx::query!(
+        r#"select website_id as "website_id!", state as "state!", created_at as "created_at!"
+        from (
+            select website_id, state, created_at from website_state
+            where website_id = any($1) and created_at >= $2
+            union all
+            (select distinct on (website_id) website_id, state, created_at from website_state
+            where website_id = any($1) and created_at < $2
+            order by website_id, created_at desc)
+        ) as s
+        order by website_id, created_at"#,
+        ids,
+        from
+    )
+    .fetch_all(db)
+    .await
+    .unwrap_or_default();
+
+    let now = Utc::now();
+    let mut spans: HashMap<Uuid, Vec<Span>> = HashMap::new();
+    for state in states {
+        let website = spans.entry(state.website_id).or_default();
+        if let Some(last) = website.last_mut() {
+            last.to = state.created_at;
+        }
+        website.push(Span {
+            state: state.state,
+            from: state.created_at,
+            to: now,
+        });
+    }
+    spans
+}
+
+// share of the time up between from and to, maintenance and paused time are left out.
+// None when nothing was monitored in that period
+pub fn ratio(spans: &[Span], from: DateTime<Utc>, to: DateTime<Utc>) -> Option<f64> {
+    let mut up = 0;
+    let mut counted = 0;
+    for span in spans {
+        let len = (span.to.min(to) - span.from.max(from)).num_seconds();
+        if len <= 0 {
+            continue;
+        }
+        match span.state.as_str() {
+            "up" => {
+                up += len;
+                counted += len;
+            }
+            "maintenance" | "paused" | "created" => {}
+            _ => counted += len,
+        }
+    }
+    if counted == 0 {
+        None
+    } else {
+        Some(up as f64 / counted as f64)
+    }
+}
+
+// one ratio per day, oldest first, the last one is today so far
+pub fn daily(spans: &[Span], days: i64) -> Vec<(DateTime<Utc>, Option<f64>)> {
+    let today = Utc.from_utc_datetime(&Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap());
+    (0..days)
+        .rev()
+        .map(|ago| {
+            let from = today - Duration::days(ago);
+            (from, ratio(spans, from, from + Duration::days(1)))
+        })
+        .collect()
+}
+
+pub fn percent(ratio: Option<f64>) -> String {
+    match ratio {
+        Some(ratio) => format!("{:.2}%", ratio * 100f64),
+        None => "-".to_string(),
+    }
+}
+
+// response times are summed per hour so averages don't need a row per check
+pub async fn record_response(db: &Pool<Postgres>, website_id: Uuid, duration: i64) {
+    sqlx::query!(
+        r#"insert into website_response (website_id, hour, total_ms, checks)
+        values ($1, date_trunc('hour', now()), $2, 1)
+        on conflict (website_id, hour) do update
+            set total_ms = website_response.total_ms + excluded.total_ms,
+                checks = website_response.checks + 1"#,
+        website_id,
+        duration
+    )
+    .execute(db)
+    .await
+    .unwrap();
+}
+
+// average response time in milliseconds since `from`
+pub async fn average_response(
+    db: &Pool<Postgres>,
+    website_id: Uuid,
+    from: DateTime<Utc>,
+) -> Option<i64> {
+    sqlx::query!(
+        r#"select (sum(total_ms) / nullif(sum(checks), 0))::bigint as average
+        from website_response where website_id = $1 and hour >= date_trunc('hour', $2::timestamptz)"#,
+        website_id,
+        from
+    )
+    .fetch_one(db)
+    .await
+    .ok()
+    .and_then(|r| r.average)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    fn span(state: &str, from: &str, to: &str) -> Span {
+        Span {
+            state: state.to_string(),
+            from: at(from),
+            to: at(to),
+        }
+    }
+
+    #[test]
+    fn ratio_of_up_time() {
+        let spans = vec![
+            span("up", "2024-01-01T00:00:00Z", "2024-01-01T18:00:00Z"),
+            span("down", "2024-01-01T18:00:00Z", "2024-01-01T20:00:00Z"),
+            span("degraded", "2024-01-01T20:00:00Z", "2024-01-02T00:00:00Z"),
+        ];
+        let ratio = ratio(&spans, at("2024-01-01T00:00:00Z"), at("2024-01-02T00:00:00Z"));
+        assert_eq!(ratio, Some(0.75));
+    }
+
+    #[test]
+    fn ratio_leaves_out_maintenance_and_paused() {
+        let spans = vec![
+            span("created", "2024-01-01T00:00:00Z", "2024-01-01T02:00:00Z"),
+            span("up", "2024-01-01T02:00:00Z", "2024-01-01T08:00:00Z"),
+            span("maintenance", "2024-01-01T08:00:00Z", "2024-01-01T12:00:00Z"),
+            span("down", "2024-01-01T12:00:00Z", "2024-01-01T14:00:00Z"),
+            span("paused", "2024-01-01T14:00:00Z", "2024-01-02T00:00:00Z"),
+        ];
+        let ratio = ratio(&spans, at("2024-01-01T00:00:00Z"), at("2024-01-02T00:00:00Z"));
+        assert_eq!(ratio, Some(0.75));
+    }
+
+    #[test]
+    fn ratio_clips_spans_to_the_period() {
+        let spans = vec![
+            span("down", "2023-12-31T00:00:00Z", "2024-01-01T06:00:00Z"),
+            span("up", "2024-01-01T06:00:00Z", "2024-01-03T00:00:00Z"),
+        ];
+        let ratio = ratio(&spans, at("2024-01-01T00:00:00Z"), at("2024-01-02T00:00:00Z"));
+        assert_eq!(ratio, Some(0.75));
+    }
+
+    #[test]
+    fn ratio_without_monitored_time() {
+        let spans = vec![
+            span("up", "2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z"),
+            span("paused", "2024-01-02T00:00:00Z", "2024-01-03T00:00:00Z"),
+        ];
+        assert_eq!(ratio(&spans, at("2024-01-02T00:00:00Z"), at("2024-01-03T00:00:00Z")), None);
+        assert_eq!(ratio(&spans, at("2024-01-05T00:00:00Z"), at("2024-01-06T00:00:00Z")), None);
+        assert_eq!(ratio(&[], at("2024-01-01T00:00:00Z"), at("2024-01-02T00:00:00Z")), None);
+    }
+}